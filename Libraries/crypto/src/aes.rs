@@ -0,0 +1,536 @@
+use maldev_error::CryptoError;
+
+const BLOCK_SIZE: usize = 16;
+const MAX_ROUNDS: usize = 14;
+
+// Round constants used by the key schedule
+const RCON: [u8; 10] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1B, 0x36];
+
+/// AES block cipher supporting 128 and 256 bit keys.
+///
+/// The round keys are expanded once at construction. Block operations use the AES-NI instructions
+/// when the CPU supports them and fall back to a software implementation whose S-box is computed
+/// on bit planes of the whole state, so no secret-dependent table lookups are performed.
+#[derive(Clone)]
+pub struct Aes {
+    rounds: usize,
+    enc_round_keys: [[u8; BLOCK_SIZE]; MAX_ROUNDS + 1],
+    dec_round_keys: [[u8; BLOCK_SIZE]; MAX_ROUNDS + 1], // InvMixColumns(enc_round_keys), only used by AES-NI
+    aes_ni: bool,
+}
+
+impl Aes {
+    /// Expands the key and selects the fastest backend available.
+    ///
+    /// # Arguments
+    /// * `key` - A 16 byte (AES-128) or 32 byte (AES-256) key.
+    ///
+    /// # Returns
+    /// * `Ok(Aes)` - The initialized cipher.
+    /// * `Err(CryptoError)` - If the key length is not supported.
+    pub fn new(key: &[u8]) -> Result<Self, CryptoError> {
+        let mut aes = Self::new_soft(key)?;
+        aes.aes_ni = aes_ni_available();
+        Ok(aes)
+    }
+
+    /// Same as [`Aes::new`] but always uses the software backend.
+    pub fn new_soft(key: &[u8]) -> Result<Self, CryptoError> {
+        let rounds = match key.len() {
+            16 => 10,
+            32 => 14,
+            _ => return Err(CryptoError::InvalidKeyLength),
+        };
+
+        let enc_round_keys = expand_key(key, rounds);
+        let mut dec_round_keys = enc_round_keys;
+        for round_key in dec_round_keys.iter_mut().take(rounds).skip(1) {
+            inv_mix_columns(round_key);
+        }
+
+        Ok(Self { rounds, enc_round_keys, dec_round_keys, aes_ni: false })
+    }
+
+    /// Returns `true` if block operations are dispatched to the AES-NI instructions.
+    pub fn uses_aes_ni(&self) -> bool {
+        self.aes_ni
+    }
+
+    /// Encrypts a single 16 byte block in place.
+    pub fn encrypt_block(&self, block: &mut [u8; BLOCK_SIZE]) {
+        #[cfg(target_arch = "x86_64")]
+        if self.aes_ni {
+            unsafe { ni::encrypt_block(&self.enc_round_keys[..=self.rounds], block) };
+            return;
+        }
+
+        add_round_key(block, &self.enc_round_keys[0]);
+        for round in 1..self.rounds {
+            sub_bytes(block);
+            shift_rows(block);
+            mix_columns(block);
+            add_round_key(block, &self.enc_round_keys[round]);
+        }
+        sub_bytes(block);
+        shift_rows(block);
+        add_round_key(block, &self.enc_round_keys[self.rounds]);
+    }
+
+    /// Decrypts a single 16 byte block in place.
+    pub fn decrypt_block(&self, block: &mut [u8; BLOCK_SIZE]) {
+        #[cfg(target_arch = "x86_64")]
+        if self.aes_ni {
+            unsafe { ni::decrypt_block(&self.dec_round_keys[..=self.rounds], block) };
+            return;
+        }
+
+        add_round_key(block, &self.enc_round_keys[self.rounds]);
+        for round in (1..self.rounds).rev() {
+            inv_shift_rows(block);
+            inv_sub_bytes(block);
+            add_round_key(block, &self.enc_round_keys[round]);
+            inv_mix_columns(block);
+        }
+        inv_shift_rows(block);
+        inv_sub_bytes(block);
+        add_round_key(block, &self.enc_round_keys[0]);
+    }
+
+    /// Encrypts or decrypts `data` in place using CTR mode.
+    ///
+    /// The counter block is incremented as a 128 bit big-endian integer (NIST SP 800-38A), so the
+    /// same call is used for both directions.
+    ///
+    /// # Arguments
+    /// * `iv` - The initial counter block.
+    /// * `data` - The buffer to transform. It can have any length.
+    pub fn apply_ctr(&self, iv: &[u8; BLOCK_SIZE], data: &mut [u8]) {
+        let mut counter = *iv;
+        for chunk in data.chunks_mut(BLOCK_SIZE) {
+            let mut keystream = counter;
+            self.encrypt_block(&mut keystream);
+            for (byte, key) in chunk.iter_mut().zip(keystream.iter()) {
+                *byte ^= key;
+            }
+            increment_counter(&mut counter);
+        }
+    }
+
+    /// Encrypts `data` using CBC mode with PKCS#7 padding.
+    ///
+    /// # Arguments
+    /// * `iv` - The initialization vector.
+    /// * `data` - The plaintext.
+    ///
+    /// # Returns
+    /// * `Vec<u8>` - The ciphertext, always a non-zero multiple of 16 bytes.
+    pub fn encrypt_cbc(&self, iv: &[u8; BLOCK_SIZE], data: &[u8]) -> Vec<u8> {
+        let padding = BLOCK_SIZE - data.len() % BLOCK_SIZE;
+        let mut output = Vec::with_capacity(data.len() + padding);
+        output.extend_from_slice(data);
+        output.resize(data.len() + padding, padding as u8);
+
+        let mut previous = *iv;
+        for chunk in output.chunks_exact_mut(BLOCK_SIZE) {
+            let mut block = [0u8; BLOCK_SIZE];
+            for i in 0..BLOCK_SIZE {
+                block[i] = chunk[i] ^ previous[i];
+            }
+            self.encrypt_block(&mut block);
+            chunk.copy_from_slice(&block);
+            previous = block;
+        }
+        output
+    }
+
+    /// Decrypts `data` using CBC mode and strips the PKCS#7 padding.
+    ///
+    /// CBC alone does not protect the ciphertext: a peer that can tell whether decryption failed
+    /// can recover the plaintext through the padding (padding oracle). Authenticate the IV and the
+    /// ciphertext before calling this function, e.g. encrypt-then-MAC with [`crate::hmac::HmacSha256`]
+    /// and a separate key. The padding is checked without data-dependent branches and every
+    /// failure returns the same error as a second line of defence.
+    ///
+    /// # Arguments
+    /// * `iv` - The initialization vector used during encryption.
+    /// * `data` - The ciphertext.
+    ///
+    /// # Returns
+    /// * `Ok(Vec<u8>)` - The plaintext.
+    /// * `Err(CryptoError::InvalidPadding)` - If the ciphertext length or the padding is invalid.
+    pub fn decrypt_cbc(&self, iv: &[u8; BLOCK_SIZE], data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        if data.is_empty() || !data.len().is_multiple_of(BLOCK_SIZE) {
+            return Err(CryptoError::InvalidPadding);
+        }
+
+        let mut output = data.to_vec();
+        let mut previous = *iv;
+        for chunk in output.chunks_exact_mut(BLOCK_SIZE) {
+            let mut block = [0u8; BLOCK_SIZE];
+            block.copy_from_slice(chunk);
+            let ciphertext = block;
+            self.decrypt_block(&mut block);
+            for i in 0..BLOCK_SIZE {
+                chunk[i] = block[i] ^ previous[i];
+            }
+            previous = ciphertext;
+        }
+
+        // Fold the whole last block, masks are 0xFF when the condition holds and 0 otherwise
+        let last_block = &output[output.len() - BLOCK_SIZE..];
+        let padding = last_block[BLOCK_SIZE - 1] as u16;
+        let mut invalid = ((padding.wrapping_sub(1) >> 8) as u8) // padding == 0
+            | (((BLOCK_SIZE as u16).wrapping_sub(padding) >> 8) as u8); // padding > 16
+        for (i, &byte) in last_block.iter().rev().enumerate() {
+            let in_padding = ((i as u16).wrapping_sub(padding) >> 8) as u8; // i < padding
+            invalid |= in_padding & (byte ^ padding as u8);
+        }
+        if invalid != 0 {
+            return Err(CryptoError::InvalidPadding);
+        }
+        output.truncate(output.len() - padding as usize);
+        Ok(output)
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn aes_ni_available() -> bool {
+    std::is_x86_feature_detected!("aes") && std::is_x86_feature_detected!("sse2")
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn aes_ni_available() -> bool {
+    false
+}
+
+fn increment_counter(counter: &mut [u8; BLOCK_SIZE]) {
+    for byte in counter.iter_mut().rev() {
+        *byte = byte.wrapping_add(1);
+        if *byte != 0 {
+            break;
+        }
+    }
+}
+
+/// Expands the cipher key into `rounds + 1` round keys (FIPS-197 section 5.2).
+fn expand_key(key: &[u8], rounds: usize) -> [[u8; BLOCK_SIZE]; MAX_ROUNDS + 1] {
+    let nk = key.len() / 4;
+    let total_words = 4 * (rounds + 1);
+    let mut words = [[0u8; 4]; 4 * (MAX_ROUNDS + 1)];
+
+    for (i, word) in key.chunks_exact(4).enumerate() {
+        words[i].copy_from_slice(word);
+    }
+
+    for i in nk..total_words {
+        let mut temp = words[i - 1];
+        if i % nk == 0 {
+            temp.rotate_left(1);
+            sub_word(&mut temp);
+            temp[0] ^= RCON[i / nk - 1];
+        } else if nk > 6 && i % nk == 4 {
+            sub_word(&mut temp);
+        }
+        for j in 0..4 {
+            words[i][j] = words[i - nk][j] ^ temp[j];
+        }
+    }
+
+    let mut round_keys = [[0u8; BLOCK_SIZE]; MAX_ROUNDS + 1];
+    for (round, round_key) in round_keys.iter_mut().enumerate().take(rounds + 1) {
+        for column in 0..4 {
+            round_key[4 * column..4 * column + 4].copy_from_slice(&words[4 * round + column]);
+        }
+    }
+    round_keys
+}
+
+fn sub_word(word: &mut [u8; 4]) {
+    let mut block = [0u8; BLOCK_SIZE];
+    block[..4].copy_from_slice(word);
+    sub_bytes(&mut block);
+    word.copy_from_slice(&block[..4]);
+}
+
+fn add_round_key(state: &mut [u8; BLOCK_SIZE], round_key: &[u8; BLOCK_SIZE]) {
+    for (byte, key) in state.iter_mut().zip(round_key.iter()) {
+        *byte ^= key;
+    }
+}
+
+// The state is stored column by column: state[4 * column + row]
+fn shift_rows(state: &mut [u8; BLOCK_SIZE]) {
+    let copy = *state;
+    for column in 0..4 {
+        for row in 0..4 {
+            state[4 * column + row] = copy[4 * ((column + row) % 4) + row];
+        }
+    }
+}
+
+fn inv_shift_rows(state: &mut [u8; BLOCK_SIZE]) {
+    let copy = *state;
+    for column in 0..4 {
+        for row in 0..4 {
+            state[4 * ((column + row) % 4) + row] = copy[4 * column + row];
+        }
+    }
+}
+
+// Multiplication by x in GF(2^8) without branching on the value
+fn xtime(b: u8) -> u8 {
+    (b << 1) ^ (0u8.wrapping_sub(b >> 7) & 0x1B)
+}
+
+fn mix_columns(state: &mut [u8; BLOCK_SIZE]) {
+    for column in state.chunks_exact_mut(4) {
+        let [a0, a1, a2, a3] = [column[0], column[1], column[2], column[3]];
+        let all = a0 ^ a1 ^ a2 ^ a3;
+        column[0] ^= all ^ xtime(a0 ^ a1);
+        column[1] ^= all ^ xtime(a1 ^ a2);
+        column[2] ^= all ^ xtime(a2 ^ a3);
+        column[3] ^= all ^ xtime(a3 ^ a0);
+    }
+}
+
+fn inv_mix_columns(state: &mut [u8; BLOCK_SIZE]) {
+    // InvMixColumns = MixColumns * {05 00 04 00} pre-multiplication (FIPS-197 optimization)
+    for column in state.chunks_exact_mut(4) {
+        let u = xtime(xtime(column[0] ^ column[2]));
+        let v = xtime(xtime(column[1] ^ column[3]));
+        column[0] ^= u;
+        column[1] ^= v;
+        column[2] ^= u;
+        column[3] ^= v;
+    }
+    mix_columns(state);
+}
+
+type BitPlanes = [u16; 8];
+
+// Bit `j` of plane `i` is the bit `i` of the state byte `j`
+fn to_planes(state: &[u8; BLOCK_SIZE]) -> BitPlanes {
+    let mut planes = [0u16; 8];
+    for (j, &byte) in state.iter().enumerate() {
+        for (i, plane) in planes.iter_mut().enumerate() {
+            *plane |= (((byte >> i) & 1) as u16) << j;
+        }
+    }
+    planes
+}
+
+fn from_planes(planes: &BitPlanes, state: &mut [u8; BLOCK_SIZE]) {
+    for (j, byte) in state.iter_mut().enumerate() {
+        *byte = 0;
+        for (i, plane) in planes.iter().enumerate() {
+            *byte |= (((plane >> j) & 1) as u8) << i;
+        }
+    }
+}
+
+/// Multiplies 16 pairs of GF(2^8) elements at once, modulo x^8 + x^4 + x^3 + x + 1.
+fn gf_mul_planes(a: &BitPlanes, b: &BitPlanes) -> BitPlanes {
+    let mut product = [0u16; 15];
+    for i in 0..8 {
+        for j in 0..8 {
+            product[i + j] ^= a[i] & b[j];
+        }
+    }
+    for k in (8..15).rev() {
+        product[k - 4] ^= product[k];
+        product[k - 5] ^= product[k];
+        product[k - 7] ^= product[k];
+        product[k - 8] ^= product[k];
+    }
+    let mut result = [0u16; 8];
+    result.copy_from_slice(&product[..8]);
+    result
+}
+
+/// Computes x^254, which is the multiplicative inverse in GF(2^8) (and maps 0 to 0).
+fn gf_inv_planes(x: &BitPlanes) -> BitPlanes {
+    let x2 = gf_mul_planes(x, x);
+    let x3 = gf_mul_planes(&x2, x);
+    let x6 = gf_mul_planes(&x3, &x3);
+    let x7 = gf_mul_planes(&x6, x);
+    let x14 = gf_mul_planes(&x7, &x7);
+    let x15 = gf_mul_planes(&x14, x);
+    let x30 = gf_mul_planes(&x15, &x15);
+    let x31 = gf_mul_planes(&x30, x);
+    let x62 = gf_mul_planes(&x31, &x31);
+    let x63 = gf_mul_planes(&x62, x);
+    let x126 = gf_mul_planes(&x63, &x63);
+    let x127 = gf_mul_planes(&x126, x);
+    gf_mul_planes(&x127, &x127)
+}
+
+fn constant_plane(constant: u8, bit: usize) -> u16 {
+    0u16.wrapping_sub(((constant >> bit) & 1) as u16)
+}
+
+fn sub_bytes(state: &mut [u8; BLOCK_SIZE]) {
+    let inverse = gf_inv_planes(&to_planes(state));
+    let mut planes = [0u16; 8];
+    for (i, plane) in planes.iter_mut().enumerate() {
+        *plane = inverse[i]
+            ^ inverse[(i + 4) % 8]
+            ^ inverse[(i + 5) % 8]
+            ^ inverse[(i + 6) % 8]
+            ^ inverse[(i + 7) % 8]
+            ^ constant_plane(0x63, i);
+    }
+    from_planes(&planes, state);
+}
+
+fn inv_sub_bytes(state: &mut [u8; BLOCK_SIZE]) {
+    let input = to_planes(state);
+    let mut planes = [0u16; 8];
+    for (i, plane) in planes.iter_mut().enumerate() {
+        *plane = input[(i + 2) % 8] ^ input[(i + 5) % 8] ^ input[(i + 7) % 8] ^ constant_plane(0x05, i);
+    }
+    from_planes(&gf_inv_planes(&planes), state);
+}
+
+#[cfg(target_arch = "x86_64")]
+mod ni {
+    use std::arch::x86_64::{__m128i, _mm_aesdec_si128, _mm_aesdeclast_si128, _mm_aesenc_si128, _mm_aesenclast_si128, _mm_loadu_si128, _mm_storeu_si128, _mm_xor_si128};
+
+    use super::BLOCK_SIZE;
+
+    #[target_feature(enable = "aes,sse2")]
+    pub(super) unsafe fn encrypt_block(round_keys: &[[u8; BLOCK_SIZE]], block: &mut [u8; BLOCK_SIZE]) {
+        let last = round_keys.len() - 1;
+        let mut state = _mm_loadu_si128(block.as_ptr() as *const __m128i);
+        state = _mm_xor_si128(state, load(&round_keys[0]));
+        for round_key in &round_keys[1..last] {
+            state = _mm_aesenc_si128(state, load(round_key));
+        }
+        state = _mm_aesenclast_si128(state, load(&round_keys[last]));
+        _mm_storeu_si128(block.as_mut_ptr() as *mut __m128i, state);
+    }
+
+    // Expects the round keys of the equivalent inverse cipher (InvMixColumns applied to rounds 1..Nr-1)
+    #[target_feature(enable = "aes,sse2")]
+    pub(super) unsafe fn decrypt_block(round_keys: &[[u8; BLOCK_SIZE]], block: &mut [u8; BLOCK_SIZE]) {
+        let last = round_keys.len() - 1;
+        let mut state = _mm_loadu_si128(block.as_ptr() as *const __m128i);
+        state = _mm_xor_si128(state, load(&round_keys[last]));
+        for round_key in round_keys[1..last].iter().rev() {
+            state = _mm_aesdec_si128(state, load(round_key));
+        }
+        state = _mm_aesdeclast_si128(state, load(&round_keys[0]));
+        _mm_storeu_si128(block.as_mut_ptr() as *mut __m128i, state);
+    }
+
+    #[target_feature(enable = "sse2")]
+    unsafe fn load(round_key: &[u8; BLOCK_SIZE]) -> __m128i {
+        _mm_loadu_si128(round_key.as_ptr() as *const __m128i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::hex;
+
+    fn block(s: &str) -> [u8; BLOCK_SIZE] {
+        hex(s).try_into().unwrap()
+    }
+
+    fn backends(key: &[u8]) -> Vec<Aes> {
+        vec![Aes::new_soft(key).unwrap(), Aes::new(key).unwrap()]
+    }
+
+    #[test]
+    fn test_sbox_values() {
+        let mut state = [0x00, 0x01, 0x53, 0xFF, 0x10, 0x20, 0x30, 0x40, 0x50, 0x60, 0x70, 0x80, 0x90, 0xA0, 0xB0, 0xC0];
+        sub_bytes(&mut state);
+        assert_eq!(state, [0x63, 0x7C, 0xED, 0x16, 0xCA, 0xB7, 0x04, 0x09, 0x53, 0xD0, 0x51, 0xCD, 0x60, 0xE0, 0xE7, 0xBA]);
+        inv_sub_bytes(&mut state);
+        assert_eq!(state, [0x00, 0x01, 0x53, 0xFF, 0x10, 0x20, 0x30, 0x40, 0x50, 0x60, 0x70, 0x80, 0x90, 0xA0, 0xB0, 0xC0]);
+    }
+
+    #[test]
+    fn test_aes128_fips197() {
+        for aes in backends(&hex("000102030405060708090a0b0c0d0e0f")) {
+            let mut data = block("00112233445566778899aabbccddeeff");
+            aes.encrypt_block(&mut data);
+            assert_eq!(data, block("69c4e0d86a7b0430d8cdb78070b4c55a"));
+            aes.decrypt_block(&mut data);
+            assert_eq!(data, block("00112233445566778899aabbccddeeff"));
+        }
+    }
+
+    #[test]
+    fn test_aes256_fips197() {
+        for aes in backends(&hex("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f")) {
+            let mut data = block("00112233445566778899aabbccddeeff");
+            aes.encrypt_block(&mut data);
+            assert_eq!(data, block("8ea2b7ca516745bfeafc49904b496089"));
+            aes.decrypt_block(&mut data);
+            assert_eq!(data, block("00112233445566778899aabbccddeeff"));
+        }
+    }
+
+    #[test]
+    fn test_invalid_key_length() {
+        assert!(Aes::new(&[0u8; 24]).is_err());
+    }
+
+    #[test]
+    fn test_ctr_sp800_38a() {
+        let plaintext = hex("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51");
+        for aes in backends(&hex("2b7e151628aed2a6abf7158809cf4f3c")) {
+            let mut data = plaintext.clone();
+            aes.apply_ctr(&block("f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff"), &mut data);
+            assert_eq!(data, hex("874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff"));
+            aes.apply_ctr(&block("f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff"), &mut data);
+            assert_eq!(data, plaintext);
+        }
+    }
+
+    #[test]
+    fn test_ctr_counter_wraps() {
+        let mut counter = block("000000000000000000000000ffffffff");
+        increment_counter(&mut counter);
+        assert_eq!(counter, block("00000000000000000000000100000000"));
+    }
+
+    #[test]
+    fn test_cbc_sp800_38a() {
+        let plaintext = hex("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51");
+        for aes in backends(&hex("2b7e151628aed2a6abf7158809cf4f3c")) {
+            let iv = block("000102030405060708090a0b0c0d0e0f");
+            let ciphertext = aes.encrypt_cbc(&iv, &plaintext);
+            assert_eq!(ciphertext.len(), 48); // A full block of padding is appended
+            assert_eq!(ciphertext[..32], hex("7649abac8119b246cee98e9b12e9197d5086cb9b507219ee95db113a917678b2"));
+            assert_eq!(aes.decrypt_cbc(&iv, &ciphertext).unwrap(), plaintext);
+        }
+    }
+
+    #[test]
+    fn test_cbc_rejects_bad_input() {
+        let aes = Aes::new(&[0x42u8; 32]).unwrap();
+        let iv = [0u8; BLOCK_SIZE];
+        assert_eq!(aes.decrypt_cbc(&iv, &[0u8; 15]), Err(CryptoError::InvalidPadding));
+        assert_eq!(aes.decrypt_cbc(&iv, &[]), Err(CryptoError::InvalidPadding));
+
+        // The first ciphertext block alone decrypts to the chosen plaintext, used as the padding
+        let mut full_block = [0x10u8; BLOCK_SIZE];
+        assert_eq!(aes.decrypt_cbc(&iv, &aes.encrypt_cbc(&iv, &full_block)[..BLOCK_SIZE]).unwrap(), b"");
+        for last in [0x00, 0x11, 0xFF] {
+            full_block[BLOCK_SIZE - 1] = last;
+            let ciphertext = aes.encrypt_cbc(&iv, &full_block);
+            assert_eq!(aes.decrypt_cbc(&iv, &ciphertext[..BLOCK_SIZE]), Err(CryptoError::InvalidPadding));
+        }
+        full_block = [0x02u8; BLOCK_SIZE];
+        full_block[BLOCK_SIZE - 2] = 0x03;
+        let ciphertext = aes.encrypt_cbc(&iv, &full_block);
+        assert_eq!(aes.decrypt_cbc(&iv, &ciphertext[..BLOCK_SIZE]), Err(CryptoError::InvalidPadding));
+
+        let mut ciphertext = aes.encrypt_cbc(&iv, b"RustMalDev");
+        let last = ciphertext.len() - 1;
+        ciphertext[last] ^= 0x01;
+        assert_eq!(aes.decrypt_cbc(&iv, &ciphertext), Err(CryptoError::InvalidPadding));
+    }
+}
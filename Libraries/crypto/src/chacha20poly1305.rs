@@ -0,0 +1,403 @@
+use crate::constant_time_eq;
+use maldev_error::CryptoError;
+
+pub const KEY_SIZE: usize = 32;
+pub const NONCE_SIZE: usize = 12;
+pub const TAG_SIZE: usize = 16;
+
+const CHACHA_BLOCK_SIZE: usize = 64;
+const POLY1305_BLOCK_SIZE: usize = 16;
+
+// "expand 32-byte k"
+const SIGMA: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+/// ChaCha20-Poly1305 AEAD as specified by RFC 8439.
+///
+/// Everything is implemented with additions, rotations and xors on 32 bit words, so the cipher runs
+/// in constant time on any CPU without relying on special instructions.
+#[derive(Clone)]
+pub struct ChaCha20Poly1305 {
+    key: [u8; KEY_SIZE],
+}
+
+impl ChaCha20Poly1305 {
+    pub fn new(key: &[u8; KEY_SIZE]) -> Self {
+        Self { key: *key }
+    }
+
+    /// Encrypts `data` in place and authenticates it together with `aad`.
+    ///
+    /// # Arguments
+    /// * `nonce` - A 96 bit nonce. It must never be reused with the same key.
+    /// * `aad` - Additional data that is authenticated but not encrypted.
+    /// * `data` - The plaintext, replaced by the ciphertext.
+    ///
+    /// # Returns
+    /// * `Ok([u8; TAG_SIZE])` - The authentication tag.
+    /// * `Err(CryptoError)` - If `data` is longer than the 256 GiB the block counter can cover.
+    pub fn encrypt(&self, nonce: &[u8; NONCE_SIZE], aad: &[u8], data: &mut [u8]) -> Result<[u8; TAG_SIZE], CryptoError> {
+        chacha20_apply(&self.key, nonce, 1, data)?;
+        Ok(self.compute_tag(nonce, aad, data))
+    }
+
+    /// Verifies the tag and decrypts `data` in place.
+    ///
+    /// # Arguments
+    /// * `nonce` - The nonce used during encryption.
+    /// * `aad` - The additional data used during encryption.
+    /// * `data` - The ciphertext, replaced by the plaintext on success.
+    /// * `tag` - The authentication tag returned by [`ChaCha20Poly1305::encrypt`].
+    ///
+    /// # Returns
+    /// * `Ok(())` - If the tag is valid. `data` now holds the plaintext.
+    /// * `Err(CryptoError)` - If the tag does not match or `data` is too long. `data` is left untouched.
+    pub fn decrypt(&self, nonce: &[u8; NONCE_SIZE], aad: &[u8], data: &mut [u8], tag: &[u8; TAG_SIZE]) -> Result<(), CryptoError> {
+        let expected = self.compute_tag(nonce, aad, data);
+        if !constant_time_eq(&expected, tag) {
+            return Err(CryptoError::AuthenticationFailed);
+        }
+        chacha20_apply(&self.key, nonce, 1, data)
+    }
+
+    /// Encrypts `data` and returns the ciphertext with the tag appended.
+    pub fn seal(&self, nonce: &[u8; NONCE_SIZE], aad: &[u8], data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let mut output = Vec::with_capacity(data.len() + TAG_SIZE);
+        output.extend_from_slice(data);
+        let tag = self.encrypt(nonce, aad, &mut output)?;
+        output.extend_from_slice(&tag);
+        Ok(output)
+    }
+
+    /// Reverses [`ChaCha20Poly1305::seal`], returning the plaintext if the appended tag is valid.
+    pub fn open(&self, nonce: &[u8; NONCE_SIZE], aad: &[u8], data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        if data.len() < TAG_SIZE {
+            return Err(CryptoError::InvalidInputLength);
+        }
+        let (ciphertext, tag) = data.split_at(data.len() - TAG_SIZE);
+        let mut output = ciphertext.to_vec();
+        self.decrypt(nonce, aad, &mut output, tag.try_into().unwrap())?;
+        Ok(output)
+    }
+
+    fn compute_tag(&self, nonce: &[u8; NONCE_SIZE], aad: &[u8], ciphertext: &[u8]) -> [u8; TAG_SIZE] {
+        // The one-time Poly1305 key is the first half of the block with counter 0
+        let block = chacha20_block(&self.key, nonce, 0);
+        let mut poly = Poly1305::new(block[..32].try_into().unwrap());
+
+        poly.update_padded(aad);
+        poly.update_padded(ciphertext);
+        let mut lengths = [0u8; 16];
+        lengths[..8].copy_from_slice(&(aad.len() as u64).to_le_bytes());
+        lengths[8..].copy_from_slice(&(ciphertext.len() as u64).to_le_bytes());
+        poly.update(&lengths);
+        poly.finalize()
+    }
+}
+
+/// XORs `data` with the ChaCha20 keystream starting at block `counter`.
+///
+/// # Arguments
+/// * `key` - The 256 bit key.
+/// * `nonce` - The 96 bit nonce.
+/// * `counter` - The initial block counter.
+/// * `data` - The buffer to transform in place.
+///
+/// # Returns
+/// * `Ok(())` - If `data` was transformed.
+/// * `Err(CryptoError::InvalidInputLength)` - If the 32 bit counter would wrap before the end of
+///   `data`, which would reuse keystream (RFC 8439 section 2.4). `data` is left untouched.
+pub fn chacha20_apply(key: &[u8; KEY_SIZE], nonce: &[u8; NONCE_SIZE], counter: u32, data: &mut [u8]) -> Result<(), CryptoError> {
+    let blocks = data.len().div_ceil(CHACHA_BLOCK_SIZE) as u64;
+    if counter as u64 + blocks > 1 << 32 {
+        return Err(CryptoError::InvalidInputLength);
+    }
+
+    for (i, chunk) in data.chunks_mut(CHACHA_BLOCK_SIZE).enumerate() {
+        let keystream = chacha20_block(key, nonce, counter + i as u32);
+        for (byte, key) in chunk.iter_mut().zip(keystream.iter()) {
+            *byte ^= key;
+        }
+    }
+    Ok(())
+}
+
+fn quarter_round(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(12);
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(7);
+}
+
+fn chacha20_block(key: &[u8; KEY_SIZE], nonce: &[u8; NONCE_SIZE], counter: u32) -> [u8; CHACHA_BLOCK_SIZE] {
+    let mut initial = [0u32; 16];
+    initial[..4].copy_from_slice(&SIGMA);
+    for i in 0..8 {
+        initial[4 + i] = read_u32_le(&key[4 * i..]);
+    }
+    initial[12] = counter;
+    for i in 0..3 {
+        initial[13 + i] = read_u32_le(&nonce[4 * i..]);
+    }
+
+    let mut state = initial;
+    for _ in 0..10 {
+        // Column rounds
+        quarter_round(&mut state, 0, 4, 8, 12);
+        quarter_round(&mut state, 1, 5, 9, 13);
+        quarter_round(&mut state, 2, 6, 10, 14);
+        quarter_round(&mut state, 3, 7, 11, 15);
+        // Diagonal rounds
+        quarter_round(&mut state, 0, 5, 10, 15);
+        quarter_round(&mut state, 1, 6, 11, 12);
+        quarter_round(&mut state, 2, 7, 8, 13);
+        quarter_round(&mut state, 3, 4, 9, 14);
+    }
+
+    let mut output = [0u8; CHACHA_BLOCK_SIZE];
+    for i in 0..16 {
+        output[4 * i..4 * i + 4].copy_from_slice(&state[i].wrapping_add(initial[i]).to_le_bytes());
+    }
+    output
+}
+
+/// Poly1305 one-time authenticator using 26 bit limbs (poly1305-donna).
+struct Poly1305 {
+    r: [u32; 5],
+    h: [u32; 5],
+    pad: [u32; 4],
+    buffer: [u8; POLY1305_BLOCK_SIZE],
+    buffered: usize,
+}
+
+impl Poly1305 {
+    fn new(key: &[u8; 32]) -> Self {
+        Self {
+            // r is clamped as required by the specification
+            r: [
+                read_u32_le(&key[0..]) & 0x3ffffff,
+                (read_u32_le(&key[3..]) >> 2) & 0x3ffff03,
+                (read_u32_le(&key[6..]) >> 4) & 0x3ffc0ff,
+                (read_u32_le(&key[9..]) >> 6) & 0x3f03fff,
+                (read_u32_le(&key[12..]) >> 8) & 0x00fffff,
+            ],
+            h: [0; 5],
+            pad: [read_u32_le(&key[16..]), read_u32_le(&key[20..]), read_u32_le(&key[24..]), read_u32_le(&key[28..])],
+            buffer: [0; POLY1305_BLOCK_SIZE],
+            buffered: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        if self.buffered > 0 {
+            let take = (POLY1305_BLOCK_SIZE - self.buffered).min(data.len());
+            self.buffer[self.buffered..self.buffered + take].copy_from_slice(&data[..take]);
+            self.buffered += take;
+            data = &data[take..];
+            if self.buffered < POLY1305_BLOCK_SIZE {
+                return;
+            }
+            let block = self.buffer;
+            self.process_block(&block, 1 << 24);
+            self.buffered = 0;
+        }
+
+        let mut blocks = data.chunks_exact(POLY1305_BLOCK_SIZE);
+        for block in &mut blocks {
+            self.process_block(block, 1 << 24);
+        }
+        let remainder = blocks.remainder();
+        self.buffer[..remainder.len()].copy_from_slice(remainder);
+        self.buffered = remainder.len();
+    }
+
+    // Feeds `data` followed by zeros up to the next 16 byte boundary, as the AEAD construction requires
+    fn update_padded(&mut self, data: &[u8]) {
+        self.update(data);
+        if self.buffered > 0 {
+            self.update(&[0u8; POLY1305_BLOCK_SIZE][..POLY1305_BLOCK_SIZE - self.buffered]);
+        }
+    }
+
+    fn process_block(&mut self, block: &[u8], hibit: u32) {
+        let [r0, r1, r2, r3, r4] = self.r;
+        let (s1, s2, s3, s4) = (r1 * 5, r2 * 5, r3 * 5, r4 * 5);
+
+        let h0 = self.h[0] + (read_u32_le(&block[0..]) & 0x3ffffff);
+        let h1 = self.h[1] + ((read_u32_le(&block[3..]) >> 2) & 0x3ffffff);
+        let h2 = self.h[2] + ((read_u32_le(&block[6..]) >> 4) & 0x3ffffff);
+        let h3 = self.h[3] + ((read_u32_le(&block[9..]) >> 6) & 0x3ffffff);
+        let h4 = self.h[4] + ((read_u32_le(&block[12..]) >> 8) | hibit);
+
+        let m = |a: u32, b: u32| a as u64 * b as u64;
+        let d0 = m(h0, r0) + m(h1, s4) + m(h2, s3) + m(h3, s2) + m(h4, s1);
+        let mut d1 = m(h0, r1) + m(h1, r0) + m(h2, s4) + m(h3, s3) + m(h4, s2);
+        let mut d2 = m(h0, r2) + m(h1, r1) + m(h2, r0) + m(h3, s4) + m(h4, s3);
+        let mut d3 = m(h0, r3) + m(h1, r2) + m(h2, r1) + m(h3, r0) + m(h4, s4);
+        let mut d4 = m(h0, r4) + m(h1, r3) + m(h2, r2) + m(h3, r1) + m(h4, r0);
+
+        // Partial reduction modulo 2^130 - 5
+        d1 += d0 >> 26;
+        d2 += d1 >> 26;
+        d3 += d2 >> 26;
+        d4 += d3 >> 26;
+        let mut h0 = (d0 as u32 & 0x3ffffff) + (d4 >> 26) as u32 * 5;
+        let h1 = (d1 as u32 & 0x3ffffff) + (h0 >> 26);
+        h0 &= 0x3ffffff;
+
+        self.h = [h0, h1, d2 as u32 & 0x3ffffff, d3 as u32 & 0x3ffffff, d4 as u32 & 0x3ffffff];
+    }
+
+    fn finalize(mut self) -> [u8; TAG_SIZE] {
+        if self.buffered > 0 {
+            let mut block = [0u8; POLY1305_BLOCK_SIZE];
+            block[..self.buffered].copy_from_slice(&self.buffer[..self.buffered]);
+            block[self.buffered] = 1;
+            self.process_block(&block, 0);
+        }
+
+        // Full carry propagation
+        let [mut h0, mut h1, mut h2, mut h3, mut h4] = self.h;
+        h2 += h1 >> 26;
+        h1 &= 0x3ffffff;
+        h3 += h2 >> 26;
+        h2 &= 0x3ffffff;
+        h4 += h3 >> 26;
+        h3 &= 0x3ffffff;
+        h0 += (h4 >> 26) * 5;
+        h4 &= 0x3ffffff;
+        h1 += h0 >> 26;
+        h0 &= 0x3ffffff;
+
+        // Compute h - p and select it if it did not underflow
+        let mut g0 = h0.wrapping_add(5);
+        let mut g1 = h1.wrapping_add(g0 >> 26);
+        g0 &= 0x3ffffff;
+        let mut g2 = h2.wrapping_add(g1 >> 26);
+        g1 &= 0x3ffffff;
+        let mut g3 = h3.wrapping_add(g2 >> 26);
+        g2 &= 0x3ffffff;
+        let g4 = h4.wrapping_add(g3 >> 26).wrapping_sub(1 << 26);
+        g3 &= 0x3ffffff;
+
+        let mask = (g4 >> 31).wrapping_sub(1);
+        h0 = (h0 & !mask) | (g0 & mask);
+        h1 = (h1 & !mask) | (g1 & mask);
+        h2 = (h2 & !mask) | (g2 & mask);
+        h3 = (h3 & !mask) | (g3 & mask);
+        h4 = (h4 & !mask) | (g4 & mask);
+
+        // h + pad mod 2^128
+        let words = [h0 | (h1 << 26), (h1 >> 6) | (h2 << 20), (h2 >> 12) | (h3 << 14), (h3 >> 18) | (h4 << 8)];
+        let mut tag = [0u8; TAG_SIZE];
+        let mut carry = 0u64;
+        for i in 0..4 {
+            carry += words[i] as u64 + self.pad[i] as u64;
+            tag[4 * i..4 * i + 4].copy_from_slice(&(carry as u32).to_le_bytes());
+            carry >>= 32;
+        }
+        tag
+    }
+}
+
+fn read_u32_le(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::hex;
+
+    const SUNSCREEN: &[u8] = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+
+    fn sequential_key(start: u8) -> [u8; KEY_SIZE] {
+        core::array::from_fn(|i| start + i as u8)
+    }
+
+    #[test]
+    fn test_chacha20_block_rfc8439() {
+        let nonce: [u8; NONCE_SIZE] = hex("000000090000004a00000000").try_into().unwrap();
+        let block = chacha20_block(&sequential_key(0), &nonce, 1);
+        assert_eq!(block[..32], hex("10f1e7e4d13b5915500fdd1fa32071c4c7d1f4c733c068030422aa9ac3d46c4e"));
+    }
+
+    #[test]
+    fn test_chacha20_encryption_rfc8439() {
+        let nonce: [u8; NONCE_SIZE] = hex("000000000000004a00000000").try_into().unwrap();
+        let mut data = SUNSCREEN.to_vec();
+        chacha20_apply(&sequential_key(0), &nonce, 1, &mut data).unwrap();
+        assert_eq!(data[..32], hex("6e2e359a2568f98041ba0728dd0d6981e97e7aec1d4360c20a27afccfd9fae0b"));
+        assert_eq!(data[data.len() - 2..], hex("874d"));
+    }
+
+    #[test]
+    fn test_chacha20_counter_overflow() {
+        let key = sequential_key(0);
+        let nonce = [0u8; NONCE_SIZE];
+
+        // The last block of the counter space can still be used
+        let mut data = [0u8; CHACHA_BLOCK_SIZE];
+        chacha20_apply(&key, &nonce, u32::MAX, &mut data).unwrap();
+        assert_eq!(data, chacha20_block(&key, &nonce, u32::MAX));
+
+        let mut data = [0u8; CHACHA_BLOCK_SIZE + 1];
+        assert_eq!(chacha20_apply(&key, &nonce, u32::MAX, &mut data), Err(CryptoError::InvalidInputLength));
+        assert_eq!(data, [0u8; CHACHA_BLOCK_SIZE + 1]);
+    }
+
+    #[test]
+    fn test_poly1305_rfc8439() {
+        let key: [u8; 32] = hex("85d6be7857556d337f4452fe42d506a80103808afb0db2fd4abff6af4149f51b").try_into().unwrap();
+        let mut poly = Poly1305::new(&key);
+        poly.update(b"Cryptographic Forum Research Group");
+        assert_eq!(poly.finalize().to_vec(), hex("a8061dc1305136c6c22b8baf0c0127a9"));
+    }
+
+    #[test]
+    fn test_poly1305_incremental_update() {
+        let key = sequential_key(0x20);
+        let message: Vec<u8> = (0..100u8).collect();
+
+        let mut one_shot = Poly1305::new(&key);
+        one_shot.update(&message);
+
+        let mut incremental = Poly1305::new(&key);
+        for chunk in message.chunks(7) {
+            incremental.update(chunk);
+        }
+        assert_eq!(one_shot.finalize(), incremental.finalize());
+    }
+
+    #[test]
+    fn test_aead_rfc8439() {
+        let cipher = ChaCha20Poly1305::new(&sequential_key(0x80));
+        let nonce: [u8; NONCE_SIZE] = hex("070000004041424344454647").try_into().unwrap();
+        let aad = hex("50515253c0c1c2c3c4c5c6c7");
+
+        let mut data = SUNSCREEN.to_vec();
+        let tag = cipher.encrypt(&nonce, &aad, &mut data).unwrap();
+        assert_eq!(data[..16], hex("d31a8d34648e60db7b86afbc53ef7ec2"));
+        assert_eq!(tag.to_vec(), hex("1ae10b594f09e26a7e902ecbd0600691"));
+
+        cipher.decrypt(&nonce, &aad, &mut data, &tag).unwrap();
+        assert_eq!(data, SUNSCREEN);
+    }
+
+    #[test]
+    fn test_aead_rejects_tampering() {
+        let cipher = ChaCha20Poly1305::new(&[0x11u8; KEY_SIZE]);
+        let nonce = [0x22u8; NONCE_SIZE];
+
+        let mut sealed = cipher.seal(&nonce, b"header", b"RustMalDev").unwrap();
+        assert_eq!(cipher.open(&nonce, b"header", &sealed).unwrap(), b"RustMalDev");
+        assert_eq!(cipher.open(&nonce, b"other header", &sealed), Err(CryptoError::AuthenticationFailed));
+
+        sealed[0] ^= 0x01;
+        assert!(cipher.open(&nonce, b"header", &sealed).is_err());
+        assert_eq!(cipher.open(&nonce, b"header", &sealed[..TAG_SIZE - 1]), Err(CryptoError::InvalidInputLength));
+    }
+}
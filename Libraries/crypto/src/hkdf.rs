@@ -0,0 +1,85 @@
+use crate::hmac::{hmac_sha256, HmacSha256};
+use crate::sha256::DIGEST_SIZE;
+use maldev_error::CryptoError;
+
+/// HKDF-Extract (RFC 5869): derives a pseudorandom key from the input keying material.
+///
+/// # Arguments
+/// * `salt` - Optional salt. An empty slice is replaced by a block of zeros as the RFC mandates.
+/// * `ikm` - The input keying material.
+pub fn hkdf_extract(salt: &[u8], ikm: &[u8]) -> [u8; DIGEST_SIZE] {
+    if salt.is_empty() {
+        hmac_sha256(&[0u8; DIGEST_SIZE], ikm)
+    } else {
+        hmac_sha256(salt, ikm)
+    }
+}
+
+/// HKDF-Expand (RFC 5869): fills `okm` with output keying material bound to `info`.
+///
+/// # Arguments
+/// * `prk` - A pseudorandom key, usually the output of [`hkdf_extract`].
+/// * `info` - Context and application specific information.
+/// * `okm` - The output buffer. At most 255 * 32 bytes can be produced.
+///
+/// # Returns
+/// * `Ok(())` - If `okm` was filled.
+/// * `Err(CryptoError)` - If the requested length is too large.
+pub fn hkdf_expand(prk: &[u8], info: &[u8], okm: &mut [u8]) -> Result<(), CryptoError> {
+    if okm.len() > 255 * DIGEST_SIZE {
+        return Err(CryptoError::OutputTooLong);
+    }
+
+    let mut previous: Option<[u8; DIGEST_SIZE]> = None;
+    for (i, chunk) in okm.chunks_mut(DIGEST_SIZE).enumerate() {
+        let mut mac = HmacSha256::new(prk);
+        if let Some(block) = previous {
+            mac.update(&block);
+        }
+        mac.update(info);
+        mac.update(&[i as u8 + 1]);
+        let block = mac.finalize();
+        chunk.copy_from_slice(&block[..chunk.len()]);
+        previous = Some(block);
+    }
+    Ok(())
+}
+
+/// Runs HKDF-Extract followed by HKDF-Expand.
+pub fn hkdf(salt: &[u8], ikm: &[u8], info: &[u8], okm: &mut [u8]) -> Result<(), CryptoError> {
+    hkdf_expand(&hkdf_extract(salt, ikm), info, okm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::hex;
+
+    #[test]
+    fn test_hkdf_rfc5869_case1() {
+        let ikm = [0x0b; 22];
+        let salt = hex("000102030405060708090a0b0c");
+        let info = hex("f0f1f2f3f4f5f6f7f8f9");
+
+        let prk = hkdf_extract(&salt, &ikm);
+        assert_eq!(prk.to_vec(), hex("077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5"));
+
+        let mut okm = [0u8; 42];
+        hkdf(&salt, &ikm, &info, &mut okm).unwrap();
+        assert_eq!(okm.to_vec(), hex("3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"));
+    }
+
+    #[test]
+    fn test_hkdf_rfc5869_case3() {
+        // Zero-length salt and info
+        let mut okm = [0u8; 42];
+        hkdf(&[], &[0x0b; 22], &[], &mut okm).unwrap();
+        assert_eq!(okm.to_vec(), hex("8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8"));
+    }
+
+    #[test]
+    fn test_hkdf_output_too_long() {
+        let mut okm = vec![0u8; 255 * DIGEST_SIZE + 1];
+        assert!(hkdf_expand(&[0u8; DIGEST_SIZE], b"", &mut okm).is_err());
+    }
+}
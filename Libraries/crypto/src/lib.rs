@@ -0,0 +1,21 @@
+pub mod aes;
+pub mod chacha20poly1305;
+pub mod hkdf;
+pub mod hmac;
+pub mod rc4;
+pub mod sha256;
+pub mod x25519;
+
+/// Compares two byte strings without an early exit, so the time taken does not depend on the
+/// position of the first difference. Only the lengths are compared in variable time.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+pub(crate) mod test_util {
+    /// Decodes a hex string, as the test vectors are written.
+    pub(crate) fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+}
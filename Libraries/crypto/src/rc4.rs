@@ -0,0 +1,99 @@
+use maldev_error::CryptoError;
+
+/// RC4 stream cipher.
+///
+/// Kept for compatibility with tooling that encrypts memory in place with RC4 (the same algorithm
+/// implemented by advapi32's SystemFunction032/033). It is not a secure cipher and should not be
+/// used for new protocols.
+pub struct Rc4 {
+    state: [u8; 256],
+    i: u8,
+    j: u8,
+}
+
+impl Rc4 {
+    /// Runs the key-scheduling algorithm.
+    ///
+    /// # Arguments
+    /// * `key` - A key between 1 and 256 bytes long.
+    ///
+    /// # Returns
+    /// * `Ok(Rc4)` - The initialized cipher.
+    /// * `Err(CryptoError)` - If the key is empty or longer than 256 bytes.
+    pub fn new(key: &[u8]) -> Result<Self, CryptoError> {
+        if key.is_empty() || key.len() > 256 {
+            return Err(CryptoError::InvalidKeyLength);
+        }
+
+        let mut state: [u8; 256] = core::array::from_fn(|i| i as u8);
+        let mut j: u8 = 0;
+        for i in 0..256 {
+            j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+            state.swap(i, j as usize);
+        }
+
+        Ok(Self { state, i: 0, j: 0 })
+    }
+
+    /// XORs `data` in place with the next bytes of the keystream.
+    ///
+    /// The keystream position is kept between calls, so a buffer can be processed in pieces.
+    pub fn apply_keystream(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            self.i = self.i.wrapping_add(1);
+            self.j = self.j.wrapping_add(self.state[self.i as usize]);
+            self.state.swap(self.i as usize, self.j as usize);
+            let index = self.state[self.i as usize].wrapping_add(self.state[self.j as usize]);
+            *byte ^= self.state[index as usize];
+        }
+    }
+}
+
+/// Encrypts or decrypts `data` in place with a fresh RC4 keystream derived from `key`.
+pub fn rc4_apply(key: &[u8], data: &mut [u8]) -> Result<(), CryptoError> {
+    Rc4::new(key)?.apply_keystream(data);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::hex;
+
+    #[test]
+    fn test_rc4_known_vectors() {
+        let mut data = b"Plaintext".to_vec();
+        rc4_apply(b"Key", &mut data).unwrap();
+        assert_eq!(data, hex("bbf316e8d940af0ad3"));
+
+        let mut data = b"Attack at dawn".to_vec();
+        rc4_apply(b"Secret", &mut data).unwrap();
+        assert_eq!(data, hex("45a01f645fc35b383552544b9bf5"));
+    }
+
+    #[test]
+    fn test_rc4_rfc6229_keystream() {
+        let mut keystream = [0u8; 16];
+        rc4_apply(&hex("0102030405"), &mut keystream).unwrap();
+        assert_eq!(keystream.to_vec(), hex("b2396305f03dc027ccc3524a0a1118a8"));
+    }
+
+    #[test]
+    fn test_rc4_streaming_matches_one_shot() {
+        let mut one_shot = [0u8; 40];
+        rc4_apply(b"RustMalDev", &mut one_shot).unwrap();
+
+        let mut streamed = [0u8; 40];
+        let mut rc4 = Rc4::new(b"RustMalDev").unwrap();
+        for chunk in streamed.chunks_mut(3) {
+            rc4.apply_keystream(chunk);
+        }
+        assert_eq!(one_shot, streamed);
+    }
+
+    #[test]
+    fn test_rc4_invalid_key() {
+        assert!(Rc4::new(&[]).is_err());
+        assert!(Rc4::new(&[0u8; 257]).is_err());
+    }
+}
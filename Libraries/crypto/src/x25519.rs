@@ -0,0 +1,251 @@
+use maldev_error::CryptoError;
+
+pub const KEY_SIZE: usize = 32;
+
+// The u-coordinate of the Curve25519 base point
+const BASE_POINT: [u8; KEY_SIZE] = {
+    let mut point = [0u8; KEY_SIZE];
+    point[0] = 9;
+    point
+};
+
+const MASK_51: u64 = (1 << 51) - 1;
+
+/// Element of GF(2^255 - 19) stored as five 51 bit limbs.
+#[derive(Clone, Copy)]
+struct FieldElement([u64; 5]);
+
+impl FieldElement {
+    const ZERO: Self = Self([0; 5]);
+    const ONE: Self = Self([1, 0, 0, 0, 0]);
+
+    fn from_bytes(bytes: &[u8; KEY_SIZE]) -> Self {
+        let load = |offset: usize| u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+        // The most significant bit is ignored (RFC 7748 section 5)
+        Self([
+            load(0) & MASK_51,
+            (load(6) >> 3) & MASK_51,
+            (load(12) >> 6) & MASK_51,
+            (load(19) >> 1) & MASK_51,
+            (load(24) >> 12) & MASK_51,
+        ])
+    }
+
+    fn to_bytes(self) -> [u8; KEY_SIZE] {
+        let mut h = self.carry().carry().0;
+
+        // Subtract p if h >= p, without branching
+        let mut t = h;
+        t[0] += 19;
+        for i in 0..4 {
+            t[i + 1] += t[i] >> 51;
+            t[i] &= MASK_51;
+        }
+        let select = 0u64.wrapping_sub(t[4] >> 51);
+        t[4] &= MASK_51;
+        for i in 0..5 {
+            h[i] = (h[i] & !select) | (t[i] & select);
+        }
+
+        let words = [h[0] | (h[1] << 51), (h[1] >> 13) | (h[2] << 38), (h[2] >> 26) | (h[3] << 25), (h[3] >> 39) | (h[4] << 12)];
+        let mut bytes = [0u8; KEY_SIZE];
+        for (i, word) in words.iter().enumerate() {
+            bytes[8 * i..8 * i + 8].copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    // Brings every limb back under 2^51 (plus a small excess on the first one)
+    fn carry(self) -> Self {
+        let mut h = self.0;
+        for i in 0..4 {
+            h[i + 1] += h[i] >> 51;
+            h[i] &= MASK_51;
+        }
+        h[0] += 19 * (h[4] >> 51);
+        h[4] &= MASK_51;
+        Self(h)
+    }
+
+    fn add(self, other: Self) -> Self {
+        Self(core::array::from_fn(|i| self.0[i] + other.0[i])).carry()
+    }
+
+    fn sub(self, other: Self) -> Self {
+        // Adding 2p keeps every limb positive
+        const TWO_P: [u64; 5] = [0xFFFFFFFFFFFDA, 0xFFFFFFFFFFFFE, 0xFFFFFFFFFFFFE, 0xFFFFFFFFFFFFE, 0xFFFFFFFFFFFFE];
+        Self(core::array::from_fn(|i| self.0[i] + TWO_P[i] - other.0[i])).carry()
+    }
+
+    fn mul(self, other: Self) -> Self {
+        let [a0, a1, a2, a3, a4] = self.0.map(|x| x as u128);
+        let [b0, b1, b2, b3, b4] = other.0.map(|x| x as u128);
+
+        // Limbs above 2^255 wrap around multiplied by 19
+        let r0 = a0 * b0 + 19 * (a1 * b4 + a2 * b3 + a3 * b2 + a4 * b1);
+        let r1 = a0 * b1 + a1 * b0 + 19 * (a2 * b4 + a3 * b3 + a4 * b2);
+        let r2 = a0 * b2 + a1 * b1 + a2 * b0 + 19 * (a3 * b4 + a4 * b3);
+        let r3 = a0 * b3 + a1 * b2 + a2 * b1 + a3 * b0 + 19 * (a4 * b4);
+        let r4 = a0 * b4 + a1 * b3 + a2 * b2 + a3 * b1 + a4 * b0;
+
+        Self::reduce_wide([r0, r1, r2, r3, r4])
+    }
+
+    fn square(self) -> Self {
+        self.mul(self)
+    }
+
+    fn mul_small(self, scalar: u64) -> Self {
+        Self::reduce_wide(self.0.map(|x| x as u128 * scalar as u128))
+    }
+
+    fn reduce_wide(mut r: [u128; 5]) -> Self {
+        for i in 0..4 {
+            r[i + 1] += r[i] >> 51;
+            r[i] &= MASK_51 as u128;
+        }
+        // The top carry can exceed 64 bits once multiplied by 19, so fold it while still wide
+        r[0] += 19 * (r[4] >> 51);
+        r[4] &= MASK_51 as u128;
+        r[1] += r[0] >> 51;
+        r[0] &= MASK_51 as u128;
+        Self(r.map(|x| x as u64))
+    }
+
+    fn square_times(self, times: usize) -> Self {
+        let mut result = self;
+        for _ in 0..times {
+            result = result.square();
+        }
+        result
+    }
+
+    // z^(p - 2) using the addition chain from curve25519-donna
+    fn invert(self) -> Self {
+        let z2 = self.square();
+        let z9 = z2.square_times(2).mul(self);
+        let z11 = z9.mul(z2);
+        let z2_5_0 = z11.square().mul(z9);
+        let z2_10_0 = z2_5_0.square_times(5).mul(z2_5_0);
+        let z2_20_0 = z2_10_0.square_times(10).mul(z2_10_0);
+        let z2_40_0 = z2_20_0.square_times(20).mul(z2_20_0);
+        let z2_50_0 = z2_40_0.square_times(10).mul(z2_10_0);
+        let z2_100_0 = z2_50_0.square_times(50).mul(z2_50_0);
+        let z2_200_0 = z2_100_0.square_times(100).mul(z2_100_0);
+        let z2_250_0 = z2_200_0.square_times(50).mul(z2_50_0);
+        z2_250_0.square_times(5).mul(z11)
+    }
+
+    fn conditional_swap(a: &mut Self, b: &mut Self, swap: u64) {
+        let mask = 0u64.wrapping_sub(swap);
+        for i in 0..5 {
+            let t = mask & (a.0[i] ^ b.0[i]);
+            a.0[i] ^= t;
+            b.0[i] ^= t;
+        }
+    }
+}
+
+/// The X25519 function from RFC 7748: multiplies the point `u` by the clamped `scalar`.
+///
+/// # Arguments
+/// * `scalar` - A 32 byte secret. It is clamped before use.
+/// * `u` - The u-coordinate of the peer point.
+pub fn x25519(scalar: &[u8; KEY_SIZE], u: &[u8; KEY_SIZE]) -> [u8; KEY_SIZE] {
+    let mut k = *scalar;
+    k[0] &= 248;
+    k[31] &= 127;
+    k[31] |= 64;
+
+    // Montgomery ladder (RFC 7748 section 5)
+    let x1 = FieldElement::from_bytes(u);
+    let (mut x2, mut z2) = (FieldElement::ONE, FieldElement::ZERO);
+    let (mut x3, mut z3) = (x1, FieldElement::ONE);
+    let mut swap = 0u64;
+
+    for t in (0..255).rev() {
+        let bit = ((k[t / 8] >> (t % 8)) & 1) as u64;
+        swap ^= bit;
+        FieldElement::conditional_swap(&mut x2, &mut x3, swap);
+        FieldElement::conditional_swap(&mut z2, &mut z3, swap);
+        swap = bit;
+
+        let a = x2.add(z2);
+        let aa = a.square();
+        let b = x2.sub(z2);
+        let bb = b.square();
+        let e = aa.sub(bb);
+        let c = x3.add(z3);
+        let d = x3.sub(z3);
+        let da = d.mul(a);
+        let cb = c.mul(b);
+        x3 = da.add(cb).square();
+        z3 = x1.mul(da.sub(cb).square());
+        x2 = aa.mul(bb);
+        z2 = e.mul(aa.add(e.mul_small(121665)));
+    }
+    FieldElement::conditional_swap(&mut x2, &mut x3, swap);
+    FieldElement::conditional_swap(&mut z2, &mut z3, swap);
+
+    x2.mul(z2.invert()).to_bytes()
+}
+
+/// Derives the public key that corresponds to `secret`.
+pub fn public_key(secret: &[u8; KEY_SIZE]) -> [u8; KEY_SIZE] {
+    x25519(secret, &BASE_POINT)
+}
+
+/// Computes the shared secret between our `secret` and the peer's public key.
+///
+/// # Returns
+/// * `Ok([u8; KEY_SIZE])` - The raw shared secret. It should be passed through a KDF (e.g. HKDF) before use.
+/// * `Err(CryptoError)` - If the result is all zeros, which happens when the peer sent a low order point.
+pub fn shared_secret(secret: &[u8; KEY_SIZE], peer_public: &[u8; KEY_SIZE]) -> Result<[u8; KEY_SIZE], CryptoError> {
+    let shared = x25519(secret, peer_public);
+    if shared.iter().fold(0u8, |acc, b| acc | b) == 0 {
+        return Err(CryptoError::LowOrderPoint);
+    }
+    Ok(shared)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::hex;
+
+    fn key(s: &str) -> [u8; KEY_SIZE] {
+        hex(s).try_into().unwrap()
+    }
+
+    #[test]
+    fn test_x25519_rfc7748_vector() {
+        let scalar = key("a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4");
+        let u = key("e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c");
+        assert_eq!(x25519(&scalar, &u), key("c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552"));
+    }
+
+    #[test]
+    fn test_x25519_rfc7748_iteration() {
+        assert_eq!(x25519(&BASE_POINT, &BASE_POINT), key("422c8e7a6227d7bca1350b3e2bb7279f7897b87bb6854b783c60e80311ae3079"));
+    }
+
+    #[test]
+    fn test_x25519_rfc7748_key_agreement() {
+        let alice_secret = key("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let bob_secret = key("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb");
+
+        let alice_public = public_key(&alice_secret);
+        let bob_public = public_key(&bob_secret);
+        assert_eq!(alice_public, key("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a"));
+        assert_eq!(bob_public, key("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f"));
+
+        let expected = key("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742");
+        assert_eq!(shared_secret(&alice_secret, &bob_public).unwrap(), expected);
+        assert_eq!(shared_secret(&bob_secret, &alice_public).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_x25519_rejects_low_order_point() {
+        assert!(shared_secret(&[0x42; KEY_SIZE], &[0u8; KEY_SIZE]).is_err());
+    }
+}
@@ -2,6 +2,7 @@ use std::{mem, ptr, slice};
 use std::ffi::{c_void, CStr};
 use std::mem::size_of;
 
+use maldev_error::{ApiError, HookError, OsCode};
 use windows::core::{PCSTR, s, w};
 use windows::Win32::Foundation::HWND;
 use windows::Win32::System::Memory::{PAGE_EXECUTE_READWRITE, PAGE_PROTECTION_FLAGS, VirtualProtect};
@@ -21,9 +22,14 @@ pub struct Hook {
 }
 
 impl Hook {
-    pub unsafe fn new(p_function_to_hook: *const u8, p_function_to_run: *const u8) -> Option<Self> {
+    /// Saves the bytes that the trampoline will overwrite and makes them writable.
+    ///
+    /// # Returns
+    /// * `Ok(Hook)` - The hook, ready to be passed to `install_hook`.
+    /// * `Err(HookError)` - If one of the pointers is null or VirtualProtect fails.
+    pub unsafe fn new(p_function_to_hook: *const u8, p_function_to_run: *const u8) -> Result<Self, HookError> {
         if p_function_to_hook.is_null() || p_function_to_run.is_null() {
-            return None;
+            return Err(HookError::NullPointer);
         }
 
         let mut hook = Self {
@@ -38,11 +44,9 @@ impl Hook {
         // Changing the protection to RWX to be able to modify the bytes
         // Saving the old protection to the struct (to re-place it at cleanup)
         VirtualProtect(p_function_to_hook as *const c_void, TRAMPOLINE_SIZE, PAGE_EXECUTE_READWRITE, hook.dw_old_protection)
-            .unwrap_or_else(|e| {
-                panic!("[!] Create Hook: VirtualProtect Failed With Error: {e}");
-            });
+            .map_err(|e| HookError::Api(ApiError::new("VirtualProtect", OsCode::HResult(e.code().0))))?;
 
-        Some(hook)
+        Ok(hook)
     }
 }
 
@@ -82,7 +86,11 @@ pub fn prepare_x32_trampoline(hook: &Hook) -> Vec<u8> {
     trampoline
 }
 
-pub fn remove_hook(mut hook: Hook) {
+/// Restores the original bytes of the hooked function.
+///
+/// # Returns
+/// * `Err(HookError)` - If VirtualProtect fails to restore the protection.
+pub fn remove_hook(mut hook: Hook) -> Result<(), HookError> {
     // memcpy: copying the original bytes over
     unsafe {ptr::copy_nonoverlapping(
         hook.v_original_bytes.as_ptr(),     // Source pointer
@@ -93,13 +101,12 @@ pub fn remove_hook(mut hook: Hook) {
         hook.v_original_bytes.clear();
         // setting the old memory protection back
         VirtualProtect(hook.p_function_to_hook as *const c_void, TRAMPOLINE_SIZE, PAGE_EXECUTE_READWRITE, hook.dw_old_protection)
-            .unwrap_or_else(|e| {
-                panic!("[!] Remove Hook: VirtualProtect Failed With Error: {e}");
-            });
+            .map_err(|e| HookError::Api(ApiError::new("VirtualProtect", OsCode::HResult(e.code().0))))?;
     }
     hook.p_function_to_hook = ptr::null();
     hook.p_function_to_run = ptr::null();
     hook.dw_old_protection = &mut PAGE_PROTECTION_FLAGS::default();
+    Ok(())
 }
 
 pub fn my_message_box_a(hwnd: HWND, p_text: PCSTR, p_caption: PCSTR, u_type: MESSAGEBOX_STYLE) -> MESSAGEBOX_RESULT {
@@ -146,7 +153,7 @@ mod tests {
         unsafe { MessageBoxA(HWND(0), text, caption, MB_OK | MB_ICONWARNING); }
 
         println!("[i] Removing The Hook ... ");
-        remove_hook(hook);
+        remove_hook(hook).expect("[!] Failed to remove the hook.");
         println!("[+] DONE");
 
         let text = s!("Normal MsgBox Again");
@@ -0,0 +1,396 @@
+use std::fmt;
+
+/// Coarse classification shared by every subsystem, so callers can branch on the reason of a
+/// failure without matching each crate's enum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    AccessDenied,
+    NotFound,
+    Hooked,
+    InvalidInput,
+    AuthenticationFailed,
+    PrivilegeNotHeld,
+    Other,
+}
+
+/// Status code returned by a Windows API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OsCode {
+    Win32(u32),    // GetLastError value
+    HResult(i32),  // windows::core::Error::code()
+    NtStatus(i32), // Returned by the Nt* functions
+}
+
+impl OsCode {
+    /// Returns the Win32 error code carried by the status, if any (HRESULTs built with
+    /// HRESULT_FROM_WIN32 are unwrapped).
+    pub fn win32(&self) -> Option<u32> {
+        match *self {
+            OsCode::Win32(code) => Some(code),
+            OsCode::HResult(hr) if (hr as u32) & 0xFFFF_0000 == 0x8007_0000 => Some(hr as u32 & 0xFFFF),
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        const ERROR_FILE_NOT_FOUND: u32 = 2;
+        const ERROR_ACCESS_DENIED: u32 = 5;
+        const ERROR_INVALID_PARAMETER: u32 = 87;
+        const ERROR_NOT_FOUND: u32 = 1168;
+        const ERROR_NOT_ALL_ASSIGNED: u32 = 1300;
+        const ERROR_NO_SUCH_PRIVILEGE: u32 = 1313;
+        const ERROR_NONE_MAPPED: u32 = 1332;
+        const STATUS_INVALID_CID: u32 = 0xC000_000B;
+        const STATUS_INVALID_PARAMETER: u32 = 0xC000_000D;
+        const STATUS_ACCESS_DENIED: u32 = 0xC000_0022;
+        const STATUS_PRIVILEGE_NOT_HELD: u32 = 0xC000_0061;
+        const STATUS_NOT_FOUND: u32 = 0xC000_0225;
+
+        if let Some(code) = self.win32() {
+            return match code {
+                ERROR_ACCESS_DENIED => ErrorKind::AccessDenied,
+                ERROR_FILE_NOT_FOUND | ERROR_NOT_FOUND | ERROR_NO_SUCH_PRIVILEGE | ERROR_NONE_MAPPED => ErrorKind::NotFound,
+                ERROR_INVALID_PARAMETER => ErrorKind::InvalidInput,
+                ERROR_NOT_ALL_ASSIGNED => ErrorKind::PrivilegeNotHeld,
+                _ => ErrorKind::Other,
+            };
+        }
+        match *self {
+            OsCode::NtStatus(status) => match status as u32 {
+                STATUS_ACCESS_DENIED => ErrorKind::AccessDenied,
+                STATUS_INVALID_CID | STATUS_NOT_FOUND => ErrorKind::NotFound,
+                STATUS_INVALID_PARAMETER => ErrorKind::InvalidInput,
+                STATUS_PRIVILEGE_NOT_HELD => ErrorKind::PrivilegeNotHeld,
+                _ => ErrorKind::Other,
+            },
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// A failed Windows API call: which function failed and the status it returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApiError {
+    pub api: &'static str,
+    pub code: OsCode,
+}
+
+impl ApiError {
+    pub fn new(api: &'static str, code: OsCode) -> Self {
+        Self { api, code }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.code.kind()
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.code {
+            OsCode::Win32(code) => write!(f, "{} Failed With Error: {}", self.api, code),
+            OsCode::HResult(hr) => write!(f, "{} Failed With Error: 0x{:08X}", self.api, hr),
+            OsCode::NtStatus(status) => write!(f, "{} Failed With Status: 0x{:08X}", self.api, status),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Errors of the `syscalls` crate (Hell's Gate resolution).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyscallError {
+    InvalidHash,
+    PebNotFound,
+    ModuleNotFound,
+    ExportDirectoryNotFound,
+    InvalidNtdllConfig,
+    SyscallNotFound { hash: u32 },
+    Hooked { hash: u32 },      // The export is jump-hooked and its SSN could not be recovered from its neighbours
+    NotASyscall { hash: u32 }, // The export is neither a syscall stub nor a hooked one
+}
+
+impl SyscallError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            SyscallError::InvalidHash => ErrorKind::InvalidInput,
+            SyscallError::PebNotFound
+            | SyscallError::ModuleNotFound
+            | SyscallError::ExportDirectoryNotFound
+            | SyscallError::SyscallNotFound { .. }
+            | SyscallError::NotASyscall { .. } => ErrorKind::NotFound,
+            SyscallError::Hooked { .. } => ErrorKind::Hooked,
+            SyscallError::InvalidNtdllConfig => ErrorKind::Other,
+        }
+    }
+}
+
+impl fmt::Display for SyscallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyscallError::InvalidHash => write!(f, "syscall hash is 0"),
+            SyscallError::PebNotFound => write!(f, "PEB is null"),
+            SyscallError::ModuleNotFound => write!(f, "ntdll module is null"),
+            SyscallError::ExportDirectoryNotFound => write!(f, "failed to get ntdll export directory"),
+            SyscallError::InvalidNtdllConfig => write!(f, "one of the ntdll config parameters is null"),
+            SyscallError::SyscallNotFound { hash } => write!(f, "no export matches syscall hash 0x{:08x}", hash),
+            SyscallError::Hooked { hash } => write!(f, "syscall 0x{:08x} is hooked and its SSN could not be recovered", hash),
+            SyscallError::NotASyscall { hash } => write!(f, "export 0x{:08x} is not a syscall stub", hash),
+        }
+    }
+}
+
+impl std::error::Error for SyscallError {}
+
+/// Errors of the `hooking` crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookError {
+    NullPointer,
+    Api(ApiError),
+}
+
+impl HookError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            HookError::NullPointer => ErrorKind::InvalidInput,
+            HookError::Api(e) => e.kind(),
+        }
+    }
+}
+
+impl fmt::Display for HookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HookError::NullPointer => write!(f, "function to hook or function to run is null"),
+            HookError::Api(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for HookError {}
+
+/// Errors of the `crypto` crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CryptoError {
+    InvalidKeyLength,
+    InvalidInputLength,
+    InvalidPadding,
+    AuthenticationFailed,
+    OutputTooLong,
+    LowOrderPoint,
+}
+
+impl CryptoError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            CryptoError::AuthenticationFailed => ErrorKind::AuthenticationFailed,
+            _ => ErrorKind::InvalidInput,
+        }
+    }
+}
+
+impl fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CryptoError::InvalidKeyLength => write!(f, "unsupported key length"),
+            CryptoError::InvalidInputLength => write!(f, "invalid input length"),
+            CryptoError::InvalidPadding => write!(f, "invalid padding"),
+            CryptoError::AuthenticationFailed => write!(f, "authentication tag mismatch"),
+            CryptoError::OutputTooLong => write!(f, "requested output is too long"),
+            CryptoError::LowOrderPoint => write!(f, "peer public key is a low order point"),
+        }
+    }
+}
+
+impl std::error::Error for CryptoError {}
+
+/// Errors of the compression routines in `utils`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionError {
+    InputTooLarge,
+    InvalidHeader,
+    Truncated,
+    InvalidBackReference,
+}
+
+impl CompressionError {
+    pub fn kind(&self) -> ErrorKind {
+        ErrorKind::InvalidInput
+    }
+}
+
+impl fmt::Display for CompressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompressionError::InputTooLarge => write!(f, "input is larger than the 4 GB the header can describe"),
+            CompressionError::InvalidHeader => write!(f, "invalid or inconsistent stream header"),
+            CompressionError::Truncated => write!(f, "truncated stream"),
+            CompressionError::InvalidBackReference => write!(f, "invalid back-reference"),
+        }
+    }
+}
+
+impl std::error::Error for CompressionError {}
+
+/// Errors of the `tokens` crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenError {
+    PrivilegeNotHeld,
+    InvalidSid,
+    Api(ApiError),
+}
+
+impl TokenError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            TokenError::PrivilegeNotHeld => ErrorKind::PrivilegeNotHeld,
+            TokenError::InvalidSid => ErrorKind::Other,
+            TokenError::Api(e) => e.kind(),
+        }
+    }
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenError::PrivilegeNotHeld => write!(f, "the token does not hold the privilege"),
+            TokenError::InvalidSid => write!(f, "the SID has no sub-authority"),
+            TokenError::Api(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+/// Errors of the `recon` crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReconError {
+    Api(ApiError),
+}
+
+impl ReconError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            ReconError::Api(e) => e.kind(),
+        }
+    }
+}
+
+impl fmt::Display for ReconError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReconError::Api(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ReconError {}
+
+/// Any error produced by the workspace crates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    Syscall(SyscallError),
+    Hook(HookError),
+    Crypto(CryptoError),
+    Compression(CompressionError),
+    Token(TokenError),
+    Recon(ReconError),
+}
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::Syscall(e) => e.kind(),
+            Error::Hook(e) => e.kind(),
+            Error::Crypto(e) => e.kind(),
+            Error::Compression(e) => e.kind(),
+            Error::Token(e) => e.kind(),
+            Error::Recon(e) => e.kind(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Syscall(e) => write!(f, "syscalls: {}", e),
+            Error::Hook(e) => write!(f, "hooking: {}", e),
+            Error::Crypto(e) => write!(f, "crypto: {}", e),
+            Error::Compression(e) => write!(f, "compression: {}", e),
+            Error::Token(e) => write!(f, "tokens: {}", e),
+            Error::Recon(e) => write!(f, "recon: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Syscall(e) => Some(e),
+            Error::Hook(e) => Some(e),
+            Error::Crypto(e) => Some(e),
+            Error::Compression(e) => Some(e),
+            Error::Token(e) => Some(e),
+            Error::Recon(e) => Some(e),
+        }
+    }
+}
+
+macro_rules! impl_from {
+    ($($subsystem:ident => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$subsystem> for Error {
+                fn from(e: $subsystem) -> Self {
+                    Error::$variant(e)
+                }
+            }
+        )*
+    };
+}
+
+impl_from!(
+    SyscallError => Syscall,
+    HookError => Hook,
+    CryptoError => Crypto,
+    CompressionError => Compression,
+    TokenError => Token,
+    ReconError => Recon,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_os_code_kind() {
+        assert_eq!(OsCode::Win32(5).kind(), ErrorKind::AccessDenied);
+        assert_eq!(OsCode::HResult(0x8007_0005u32 as i32).kind(), ErrorKind::AccessDenied);
+        assert_eq!(OsCode::NtStatus(0xC000_0022u32 as i32).kind(), ErrorKind::AccessDenied);
+        assert_eq!(OsCode::HResult(0x8007_0514u32 as i32).kind(), ErrorKind::PrivilegeNotHeld);
+        assert_eq!(OsCode::NtStatus(0xC000_000Bu32 as i32).kind(), ErrorKind::NotFound);
+        assert_eq!(OsCode::HResult(0x8000_4005u32 as i32).kind(), ErrorKind::Other);
+        assert_eq!(OsCode::HResult(0x8000_4005u32 as i32).win32(), None);
+    }
+
+    #[test]
+    fn test_conversions_and_kind() {
+        fn resolve() -> Result<(), Error> {
+            Err(SyscallError::Hooked { hash: 0x296c29b1 })?
+        }
+        let error = resolve().unwrap_err();
+        assert_eq!(error, Error::Syscall(SyscallError::Hooked { hash: 0x296c29b1 }));
+        assert_eq!(error.kind(), ErrorKind::Hooked);
+
+        let error: Error = TokenError::Api(ApiError::new("OpenProcess", OsCode::HResult(0x8007_0005u32 as i32))).into();
+        assert_eq!(error.kind(), ErrorKind::AccessDenied);
+        assert_eq!(error.to_string(), "tokens: OpenProcess Failed With Error: 0x80070005");
+
+        let error: Error = CryptoError::AuthenticationFailed.into();
+        assert_eq!(error.kind(), ErrorKind::AuthenticationFailed);
+
+        let error: Error = SyscallError::ModuleNotFound.into();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        assert_eq!(SyscallError::NotASyscall { hash: 0x296c29b1 }.kind(), ErrorKind::NotFound);
+        assert_eq!(HookError::NullPointer.kind(), ErrorKind::InvalidInput);
+    }
+}
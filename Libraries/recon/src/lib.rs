@@ -0,0 +1,2 @@
+pub mod processes;
+pub use processes::{processes, processes_filtered, ProcessInfo};
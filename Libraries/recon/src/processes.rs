@@ -0,0 +1,298 @@
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use windows::Wdk::System::SystemInformation::{NtQuerySystemInformation, SystemProcessInformation};
+use windows::Win32::Foundation::{CloseHandle, FILETIME, HANDLE, HMODULE, STATUS_INFO_LENGTH_MISMATCH};
+use windows::Win32::Security::TOKEN_QUERY;
+use windows::Win32::System::ProcessStatus::{EnumProcessModulesEx, GetModuleBaseNameW, LIST_MODULES_ALL};
+use windows::Win32::System::Threading::{GetProcessTimes, OpenProcess, PROCESS_ACCESS_RIGHTS, PROCESS_QUERY_INFORMATION, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_VM_READ};
+use windows::Win32::System::WindowsProgramming::SYSTEM_PROCESS_INFORMATION;
+use maldev_error::{ApiError, OsCode, ReconError};
+use tokens::Token;
+use tokens::integrity::{integrity_level, IntegrityLevel};
+use tokens::user::{lookup_sid, token_user_sid};
+
+const INITIAL_BUFFER_SIZE: usize = 0x80000;
+
+// SID -> account name, shared by the entries of one enumeration
+type AccountCache = Arc<Mutex<HashMap<String, Option<String>>>>;
+
+#[derive(Debug, Clone)]
+struct TokenDetails {
+    sid: Option<String>,
+    integrity: Option<IntegrityLevel>,
+}
+
+/// Snapshot of a running process.
+///
+/// The token owner, integrity level and loaded modules are only queried the first time they are requested, so
+/// filtering on the other fields does not open the token of every process. The process is then
+/// reopened by PID and its creation time compared with `create_time`, so an entry whose process
+/// exited and whose PID was reused reports `None` instead of the details of the new process.
+#[derive(Clone)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub ppid: u32,
+    pub image_name: String,
+    pub session_id: u32,
+    pub thread_count: u32,
+    pub handle_count: u32,
+    pub create_time: u64, // FILETIME, in 100 ns intervals since January 1, 1601 (UTC)
+    token: OnceCell<Option<TokenDetails>>, // `None` if the process token cannot be opened
+    user: OnceCell<Option<String>>,
+    modules: OnceCell<Option<Vec<String>>>, // `None` if the process cannot be opened for reading
+    accounts: AccountCache,
+}
+
+impl fmt::Debug for ProcessInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProcessInfo")
+            .field("pid", &self.pid)
+            .field("ppid", &self.ppid)
+            .field("image_name", &self.image_name)
+            .field("session_id", &self.session_id)
+            .field("thread_count", &self.thread_count)
+            .field("handle_count", &self.handle_count)
+            .field("create_time", &self.create_time)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ProcessInfo {
+    /// Returns the owner of the process token as `DOMAIN\user`, or `None` if the token cannot be
+    /// opened with the current privileges or the SID cannot be resolved.
+    pub fn user(&self) -> Option<&str> {
+        self.user
+            .get_or_init(|| {
+                let sid = self.token_details()?.sid.clone()?;
+                let mut accounts = self.accounts.lock().unwrap_or_else(|e| e.into_inner());
+                accounts.entry(sid).or_insert_with_key(|sid| lookup_sid(sid).ok()).clone()
+            })
+            .as_deref()
+    }
+
+    /// Returns the SID of the process token owner (e.g. `S-1-5-18`).
+    pub fn user_sid(&self) -> Option<&str> {
+        self.token_details()?.sid.as_deref()
+    }
+
+    /// Returns the integrity level of the process token.
+    pub fn integrity(&self) -> Option<IntegrityLevel> {
+        self.token_details()?.integrity
+    }
+
+    /// Returns the base names of the modules loaded in the process (e.g. `ntdll.dll`), both 32 and
+    /// 64 bit ones for WOW64 processes.
+    ///
+    /// Reading the module list requires `PROCESS_QUERY_INFORMATION | PROCESS_VM_READ`, so this is
+    /// `None` for protected processes and, without SeDebugPrivilege, for other users' processes.
+    pub fn modules(&self) -> Option<&[String]> {
+        self.modules.get_or_init(|| self.query_modules()).as_deref()
+    }
+
+    /// Whether a module with this base name is loaded, compared case-insensitively.
+    pub fn has_module(&self, name: &str) -> bool {
+        self.modules().is_some_and(|modules| modules.iter().any(|module| module.eq_ignore_ascii_case(name)))
+    }
+
+    fn query_modules(&self) -> Option<Vec<String>> {
+        let process = self.open(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ)?;
+
+        let mut handles: Vec<HMODULE> = vec![HMODULE::default(); 256];
+        loop {
+            let size = (handles.len() * size_of::<HMODULE>()) as u32;
+            let mut needed: u32 = 0;
+            unsafe { EnumProcessModulesEx(process.0, handles.as_mut_ptr(), size, &mut needed, LIST_MODULES_ALL) }.ok()?;
+            if needed <= size {
+                handles.truncate(needed as usize / size_of::<HMODULE>());
+                break;
+            }
+            // Modules may be loaded between two calls, leave some room
+            handles.resize(needed as usize / size_of::<HMODULE>() + 16, HMODULE::default());
+        }
+
+        let mut name = [0u16; 260];
+        Some(
+            handles
+                .iter()
+                .filter_map(|&module| {
+                    let len = unsafe { GetModuleBaseNameW(process.0, module, &mut name) } as usize;
+                    (len != 0).then(|| String::from_utf16_lossy(&name[..len]))
+                })
+                .collect(),
+        )
+    }
+
+    fn token_details(&self) -> Option<&TokenDetails> {
+        self.token
+            .get_or_init(|| {
+                let process = self.open(PROCESS_QUERY_LIMITED_INFORMATION)?;
+                let token = Token::open_process_handle(process.0, TOKEN_QUERY).ok()?;
+                Some(TokenDetails { sid: token_user_sid(&token).ok(), integrity: integrity_level(&token).ok() })
+            })
+            .as_ref()
+    }
+
+    /// Opens the process by PID, and only returns the handle if it is still the process of the
+    /// snapshot (same creation time).
+    fn open(&self, access: PROCESS_ACCESS_RIGHTS) -> Option<ProcessHandle> {
+        let process = ProcessHandle(unsafe { OpenProcess(access | PROCESS_QUERY_LIMITED_INFORMATION, false, self.pid) }.ok()?);
+
+        let mut creation = FILETIME::default();
+        let mut exit = FILETIME::default();
+        let mut kernel = FILETIME::default();
+        let mut user = FILETIME::default();
+        unsafe { GetProcessTimes(process.0, &mut creation, &mut exit, &mut kernel, &mut user) }.ok()?;
+
+        let create_time = (creation.dwHighDateTime as u64) << 32 | creation.dwLowDateTime as u64;
+        (create_time == self.create_time).then_some(process)
+    }
+}
+
+struct ProcessHandle(HANDLE);
+
+impl Drop for ProcessHandle {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.0);
+        }
+    }
+}
+
+/// Lists the running processes using NtQuerySystemInformation(SystemProcessInformation).
+///
+/// The token owner and integrity level are resolved on demand through [`ProcessInfo::user`] and
+/// [`ProcessInfo::integrity`], and the loaded modules through [`ProcessInfo::modules`] and
+/// [`ProcessInfo::has_module`]. Account names are looked up once per SID for all the entries
+/// returned by one call.
+///
+/// # Returns
+/// * `Ok(Vec<ProcessInfo>)` - One entry per process, in the order returned by the kernel.
+/// * `Err(ReconError)` - If NtQuerySystemInformation fails.
+pub fn processes() -> Result<Vec<ProcessInfo>, ReconError> {
+    processes_filtered(|_| true)
+}
+
+/// Same as [`processes`] but only keeps the entries for which `filter` returns `true`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use recon::processes_filtered;
+/// use tokens::integrity::IntegrityLevel;
+///
+/// let elevated = processes_filtered(|p| p.integrity() >= Some(IntegrityLevel::High)).unwrap();
+/// ```
+pub fn processes_filtered<F>(filter: F) -> Result<Vec<ProcessInfo>, ReconError>
+where
+    F: Fn(&ProcessInfo) -> bool,
+{
+    let buffer = query_process_information()?;
+    let accounts = AccountCache::default();
+    let mut result = Vec::new();
+
+    let mut offset = 0usize;
+    loop {
+        let entry = unsafe { &*((buffer.as_ptr() as *const u8).add(offset) as *const SYSTEM_PROCESS_INFORMATION) };
+        let info = ProcessInfo {
+            pid: entry.UniqueProcessId.0 as u32,
+            ppid: entry.Reserved2 as usize as u32, // InheritedFromUniqueProcessId
+            image_name: unicode_string_to_string(entry.ImageName.Buffer.0, entry.ImageName.Length),
+            session_id: entry.SessionId,
+            thread_count: entry.NumberOfThreads,
+            handle_count: entry.HandleCount,
+            // CreateTime is at offset 24 of Reserved1, after WorkingSetPrivateSize, HardFaultCount,
+            // NumberOfThreadsHighWatermark and CycleTime
+            create_time: u64::from_le_bytes(entry.Reserved1[24..32].try_into().unwrap()),
+            token: OnceCell::new(),
+            user: OnceCell::new(),
+            modules: OnceCell::new(),
+            accounts: Arc::clone(&accounts),
+        };
+
+        if filter(&info) {
+            result.push(info);
+        }
+
+        if entry.NextEntryOffset == 0 {
+            break;
+        }
+        offset += entry.NextEntryOffset as usize;
+    }
+
+    Ok(result)
+}
+
+fn query_process_information() -> Result<Vec<u64>, ReconError> {
+    let mut buffer_size = INITIAL_BUFFER_SIZE;
+    loop {
+        // u64 elements keep the entries correctly aligned
+        let mut buffer = vec![0u64; buffer_size.div_ceil(size_of::<u64>())];
+        let mut return_length: u32 = 0;
+        // Declare the size of the actual allocation, which is rounded up to a whole number of u64
+        let buffer_len = (buffer.len() * size_of::<u64>()) as u32;
+        let status = unsafe {
+            NtQuerySystemInformation(SystemProcessInformation, buffer.as_mut_ptr() as *mut c_void, buffer_len, &mut return_length)
+        };
+
+        if status == STATUS_INFO_LENGTH_MISMATCH {
+            // Processes may be created between two calls, leave some room
+            buffer_size = return_length as usize + 0x10000;
+            continue;
+        }
+        if status.is_err() {
+            return Err(ReconError::Api(ApiError::new("NtQuerySystemInformation", OsCode::NtStatus(status.0))));
+        }
+        return Ok(buffer);
+    }
+}
+
+fn unicode_string_to_string(buffer: *const u16, length: u16) -> String {
+    if buffer.is_null() {
+        return String::new();
+    }
+    // Length is expressed in bytes
+    let chars = unsafe { std::slice::from_raw_parts(buffer, length as usize / 2) };
+    String::from_utf16_lossy(chars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_processes_contains_current_process() {
+        let list = processes().expect("[!] Failed to enumerate processes");
+        assert!(list.len() > 1);
+
+        let current = list.iter().find(|p| p.pid == std::process::id()).expect("[!] Current process not found");
+        let exe_name = std::env::current_exe().unwrap().file_name().unwrap().to_string_lossy().to_lowercase();
+        assert_eq!(current.image_name.to_lowercase(), exe_name);
+        assert!(current.thread_count > 0);
+        assert!(current.user().is_some());
+        assert!(current.user_sid().is_some());
+        assert!(current.integrity().is_some());
+        assert!(current.has_module("NTDLL.DLL"));
+        assert!(current.modules().unwrap().iter().any(|module| module.to_lowercase() == exe_name));
+
+        println!("[i] Current Process: {:?}", current);
+    }
+
+    #[test]
+    fn test_processes_filtered() {
+        let pid = std::process::id();
+        let list = processes_filtered(|p| p.pid == pid).expect("[!] Failed to enumerate processes");
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].pid, pid);
+        // The filter only looked at the pid, so no token was opened
+        assert!(list[0].token.get().is_none());
+
+        // Same PID with another creation time, as if the process exited and its PID was reused
+        let mut stale = list[0].clone();
+        stale.create_time ^= 1;
+        assert!(stale.integrity().is_none());
+        assert!(list[0].integrity().is_some());
+    }
+}
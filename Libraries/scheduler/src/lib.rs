@@ -0,0 +1,323 @@
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Returned by a job after each run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Continue, // Keep the job scheduled (ignored by one-shot jobs)
+    Done,     // Remove the job from the scheduler
+}
+
+/// Order in which jobs that are due at the same time are run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Low,
+    Normal,
+    High,
+}
+
+/// When a job runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schedule {
+    /// Runs once after `delay`.
+    Once { delay: Duration },
+    /// Runs every `interval`, each delay being randomly moved by up to `jitter` percent (0 - 100)
+    /// of the interval in either direction. The first run happens one (jittered) interval after
+    /// the job is added.
+    Every { interval: Duration, jitter: u8 },
+}
+
+/// A unit of work driven by the [`Scheduler`]. Closures returning a [`JobStatus`] implement it.
+pub trait Job {
+    fn run(&mut self) -> JobStatus;
+}
+
+impl<F: FnMut() -> JobStatus> Job for F {
+    fn run(&mut self) -> JobStatus {
+        self()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct JobId(u64);
+
+// Due time used when `now + delay` is not representable by `Instant` (about a century away)
+const FAR_FUTURE: Duration = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+
+struct Entry {
+    id: JobId,
+    job: Box<dyn Job>,
+    schedule: Schedule,
+    priority: Priority,
+    due: Option<Instant>, // `None` if not even `now + FAR_FUTURE` is representable, never run
+}
+
+/// Cooperative single-threaded scheduler: jobs run one after the other on the thread calling
+/// [`Scheduler::run`] or [`Scheduler::run_pending`], so a long job delays the others.
+pub struct Scheduler {
+    entries: Vec<Entry>,
+    next_id: u64,
+    rng: u64,
+}
+
+impl Scheduler {
+    /// Creates an empty scheduler.
+    ///
+    /// # Arguments
+    /// * `seed` - Seed of the generator used for the jitter. 0 is replaced by a fixed non-zero value.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            entries: Vec::new(),
+            next_id: 0,
+            rng: if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed },
+        }
+    }
+
+    /// Adds a job, relative to the current time.
+    ///
+    /// # Returns
+    /// * `JobId` - The identifier to pass to [`Scheduler::cancel`].
+    pub fn add<J: Job + 'static>(&mut self, job: J, schedule: Schedule, priority: Priority) -> JobId {
+        self.add_at(job, schedule, priority, Instant::now())
+    }
+
+    /// Same as [`Scheduler::add`] with an explicit current time.
+    pub fn add_at<J: Job + 'static>(&mut self, job: J, schedule: Schedule, priority: Priority, now: Instant) -> JobId {
+        let id = JobId(self.next_id);
+        self.next_id += 1;
+        let due = deadline(now, self.delay(&schedule));
+        self.entries.push(Entry { id, job: Box::new(job), schedule, priority, due });
+        id
+    }
+
+    /// Removes a job.
+    ///
+    /// # Returns
+    /// * `true` - If the job was still scheduled.
+    pub fn cancel(&mut self, id: JobId) -> bool {
+        let len = self.entries.len();
+        self.entries.retain(|entry| entry.id != id);
+        self.entries.len() != len
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the time at which the next job is due, or `None` if no job is scheduled or none can
+    /// ever become due.
+    pub fn next_due(&self) -> Option<Instant> {
+        self.entries.iter().filter_map(|entry| entry.due).min()
+    }
+
+    /// Runs every job due at `now`, highest priority first, then earliest due first.
+    ///
+    /// Recurring jobs are rescheduled from `now`, so a late scheduler does not run them several
+    /// times in a row to catch up.
+    ///
+    /// # Returns
+    /// * `usize` - The number of jobs that were run.
+    pub fn run_pending(&mut self, now: Instant) -> usize {
+        let mut due: Vec<(Priority, Instant, JobId)> = self
+            .entries
+            .iter()
+            .filter_map(|entry| entry.due.filter(|due| *due <= now).map(|due| (entry.priority, due, entry.id)))
+            .collect();
+        due.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2 .0.cmp(&b.2 .0)));
+
+        for &(_, _, id) in &due {
+            // Ids are only removed below, so the entry is still present
+            let index = self.entries.iter().position(|entry| entry.id == id).unwrap();
+            let status = self.entries[index].job.run();
+
+            let schedule = self.entries[index].schedule;
+            match (schedule, status) {
+                (Schedule::Every { .. }, JobStatus::Continue) => {
+                    self.entries[index].due = deadline(now, self.delay(&schedule));
+                }
+                _ => {
+                    self.entries.remove(index);
+                }
+            }
+        }
+        due.len()
+    }
+
+    /// Runs the jobs on the current thread, sleeping between them, until no job is left that can
+    /// become due.
+    pub fn run(&mut self) {
+        while let Some(due) = self.next_due() {
+            let now = Instant::now();
+            if due > now {
+                thread::sleep(due - now);
+            }
+            self.run_pending(Instant::now());
+        }
+    }
+
+    fn delay(&mut self, schedule: &Schedule) -> Duration {
+        match *schedule {
+            Schedule::Once { delay } => delay,
+            Schedule::Every { interval, jitter } => {
+                let jitter = jitter.min(100) as u128;
+                let interval_ns = interval.as_nanos();
+                let spread = interval_ns * jitter / 100;
+                if spread == 0 {
+                    return interval;
+                }
+                // Uniform in [interval - spread, interval + spread]
+                // 128 random bits, so spreads wider than 2^64 ns are covered as well
+                let random = (self.next_random() as u128) << 64 | self.next_random() as u128;
+                let offset = random % (2 * spread + 1);
+                let delay_ns = interval_ns - spread + offset;
+                match u64::try_from(delay_ns / 1_000_000_000) {
+                    Ok(secs) => Duration::new(secs, (delay_ns % 1_000_000_000) as u32),
+                    Err(_) => Duration::MAX,
+                }
+            }
+        }
+    }
+
+    // xorshift64
+    fn next_random(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+}
+
+/// Returns `now + delay`, saturated to a far-future instant when it overflows `Instant`, or `None`
+/// if even that instant cannot be represented.
+fn deadline(now: Instant, delay: Duration) -> Option<Instant> {
+    now.checked_add(delay).or_else(|| now.checked_add(FAR_FUTURE.min(delay)))
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as u64;
+        Self::new(time ^ std::process::id() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn recorder(log: &Rc<RefCell<Vec<&'static str>>>, name: &'static str, status: JobStatus) -> impl FnMut() -> JobStatus {
+        let log = Rc::clone(log);
+        move || {
+            log.borrow_mut().push(name);
+            status
+        }
+    }
+
+    #[test]
+    fn test_priority_order_and_one_shot() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut scheduler = Scheduler::new(1);
+        let start = Instant::now();
+        let once = Schedule::Once { delay: Duration::from_secs(1) };
+
+        scheduler.add_at(recorder(&log, "low", JobStatus::Continue), once, Priority::Low, start);
+        scheduler.add_at(recorder(&log, "high", JobStatus::Continue), once, Priority::High, start);
+        scheduler.add_at(recorder(&log, "normal", JobStatus::Continue), once, Priority::Normal, start);
+
+        assert_eq!(scheduler.run_pending(start), 0);
+        assert_eq!(scheduler.next_due(), Some(start + Duration::from_secs(1)));
+        assert_eq!(scheduler.run_pending(start + Duration::from_secs(1)), 3);
+        assert_eq!(*log.borrow(), ["high", "normal", "low"]);
+        assert!(scheduler.is_empty());
+    }
+
+    #[test]
+    fn test_recurring_jitter_bounds() {
+        let mut scheduler = Scheduler::new(42);
+        let start = Instant::now();
+        let interval = Duration::from_secs(10);
+        scheduler.add_at(|| JobStatus::Continue, Schedule::Every { interval, jitter: 20 }, Priority::Normal, start);
+
+        let mut now = start;
+        let mut delays = Vec::new();
+        for _ in 0..50 {
+            let due = scheduler.next_due().unwrap();
+            delays.push(due - now);
+            now = due;
+            assert_eq!(scheduler.run_pending(now), 1);
+        }
+        assert!(delays.iter().all(|d| *d >= Duration::from_secs(8) && *d <= Duration::from_secs(12)));
+        assert!(delays.iter().any(|d| *d != delays[0]));
+        assert_eq!(scheduler.len(), 1);
+    }
+
+    #[test]
+    fn test_done_and_cancel() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut scheduler = Scheduler::new(7);
+        let start = Instant::now();
+        let every = Schedule::Every { interval: Duration::from_millis(100), jitter: 0 };
+
+        scheduler.add_at(recorder(&log, "done", JobStatus::Done), every, Priority::Normal, start);
+        let cancelled = scheduler.add_at(recorder(&log, "cancelled", JobStatus::Continue), every, Priority::Normal, start);
+        assert!(scheduler.cancel(cancelled));
+        assert!(!scheduler.cancel(cancelled));
+
+        scheduler.run_pending(start + Duration::from_millis(100));
+        assert_eq!(*log.borrow(), ["done"]);
+        assert!(scheduler.is_empty());
+    }
+
+    #[test]
+    fn test_large_delays_saturate() {
+        let mut scheduler = Scheduler::new(3);
+        let start = Instant::now();
+
+        scheduler.add_at(|| JobStatus::Continue, Schedule::Once { delay: Duration::MAX }, Priority::Normal, start);
+        scheduler.add_at(|| JobStatus::Continue, Schedule::Every { interval: Duration::MAX, jitter: 100 }, Priority::Normal, start);
+        assert!(scheduler.next_due().unwrap() >= start + Duration::from_secs(365 * 24 * 60 * 60));
+        assert_eq!(scheduler.run_pending(start), 0);
+
+        // A due time that cannot be represented at all never makes the job due
+        let mut unreachable = Scheduler::new(3);
+        unreachable.entries.push(Entry {
+            id: JobId(0),
+            job: Box::new(|| -> JobStatus { panic!("[!] A job without a due time ran") }),
+            schedule: Schedule::Once { delay: Duration::MAX },
+            priority: Priority::High,
+            due: None,
+        });
+        assert_eq!(unreachable.next_due(), None);
+        assert_eq!(unreachable.run_pending(start + FAR_FUTURE), 0);
+        unreachable.run();
+        assert_eq!(unreachable.len(), 1);
+
+        // interval + jitter is larger than what a Duration can hold
+        let huge = Schedule::Every { interval: Duration::from_secs(u64::MAX - 1), jitter: 100 };
+        let delays: Vec<Duration> = (0..100).map(|_| scheduler.delay(&huge)).collect();
+        assert!(delays.contains(&Duration::MAX));
+        assert!(delays.iter().any(|d| *d < Duration::from_secs(u64::MAX - 1)));
+    }
+
+    #[test]
+    fn test_run_returns_when_empty() {
+        let count = Rc::new(RefCell::new(0));
+        let mut scheduler = Scheduler::default();
+        let counter = Rc::clone(&count);
+        scheduler.add(
+            move || {
+                *counter.borrow_mut() += 1;
+                if *counter.borrow() == 3 { JobStatus::Done } else { JobStatus::Continue }
+            },
+            Schedule::Every { interval: Duration::from_millis(5), jitter: 50 },
+            Priority::Normal,
+        );
+        scheduler.run();
+        assert_eq!(*count.borrow(), 3);
+    }
+}
@@ -3,6 +3,7 @@ use std::ffi::c_void;
 use std::{ptr};
 use utils::{get_export_directory};
 use utils::hash::{compute_crc32_hash};
+use maldev_error::SyscallError;
 use windows::Win32::Foundation::HMODULE;
 use windows::Win32::System::Threading::PEB;
 use windows::Win32::System::WindowsProgramming::LDR_DATA_TABLE_ENTRY;
@@ -46,22 +47,22 @@ static mut SYSCALL_CACHE: Vec<NtSyscall> = Vec::new();
 ///
 /// # Returns
 /// * `Ok(NtdllConfig)` - If the initialization is successful with all required fields populated.
-/// * `Err(&'static str)` - If there is an error during the initialization, such as a null pointer
+/// * `Err(SyscallError)` - If there is an error during the initialization, such as a null pointer
 ///   being encountered or any field failing to be correctly initialized.
 ///
 /// # Errors
 /// The function returns the following errors:
-/// * `"init_ntdll_config_structure: PEB is null"` - If the PEB is null.
-/// * `"init_ntdll_config_structure: module is null"` - If the module base address is null.
-/// * `"Failed to get export directory"` - If the export directory cannot be fetched.
-/// * `"init_ntdll_config_structure: One of the parameters is null"` - If any of the parameters in
-///   the `NtdllConfig` structure are null after initialization.
+/// * `SyscallError::PebNotFound` - If the PEB is null.
+/// * `SyscallError::ModuleNotFound` - If the module base address is null.
+/// * `SyscallError::ExportDirectoryNotFound` - If the export directory cannot be fetched.
+/// * `SyscallError::InvalidNtdllConfig` - If any of the parameters in the `NtdllConfig` structure
+///   are null after initialization.
 
-unsafe fn init_ntdll_config_structure() -> Result<NtdllConfig, &'static str> {
+unsafe fn init_ntdll_config_structure() -> Result<NtdllConfig, SyscallError> {
     // Getting PEB
     let p_peb: *mut PEB = utils::get_peb();
     if p_peb.is_null() { // || (*p_peb).OSMajorVersion != 0xA
-        return Err("init_ntdll_config_structure: PEB is null");
+        return Err(SyscallError::PebNotFound);
     }
 
     // Getting ntdll.dll module
@@ -69,12 +70,12 @@ unsafe fn init_ntdll_config_structure() -> Result<NtdllConfig, &'static str> {
     let p_ldr = ((*p_ldr_data).Flink as *mut u8).sub(0x10) as *mut LDR_DATA_TABLE_ENTRY; //skip local image element
     let u_module = (*p_ldr).DllBase as usize;
     if u_module == 0 {
-        return Err("init_ntdll_config_structure: module is null");
+        return Err(SyscallError::ModuleNotFound);
     }
 
     // Fetching the export directory of ntdll
     let h_module = HMODULE(u_module as isize);
-    let p_img_exp_dir = get_export_directory(h_module).ok_or("Failed to get export directory")?;
+    let p_img_exp_dir = get_export_directory(h_module).ok_or(SyscallError::ExportDirectoryNotFound)?;
 
     // Initializing the NtdllConfig struct
     let config = NtdllConfig {
@@ -87,7 +88,7 @@ unsafe fn init_ntdll_config_structure() -> Result<NtdllConfig, &'static str> {
 
     // Checking
     if config.u_module == 0 || config.dw_number_of_names == 0 || config.pdw_array_of_names.is_null() || config.pdw_array_of_addresses.is_null() || config.pw_array_of_ordinals.is_null() {
-        Err("init_ntdll_config_structure: One of the parameters is null")
+        Err(SyscallError::InvalidNtdllConfig)
     } else {
         Ok(config)
     }
@@ -105,11 +106,13 @@ unsafe fn init_ntdll_config_structure() -> Result<NtdllConfig, &'static str> {
 ///
 /// # Returns
 /// * `Ok(NtSyscall)` - If the syscall is found and validated, returns the populated `NtSyscall` structure.
-/// * `Err(&'static str)` - If the syscall with the given hash is not found or validation fails.
-pub unsafe fn fetch_nt_syscall(dw_sys_hash: u32) -> Result<NtSyscall, &'static str> {
+/// * `Err(SyscallError)` - `SyscallError::SyscallNotFound` if no export matches the hash,
+///   `SyscallError::Hooked` if the export starts with a jump hook and its SSN cannot be recovered
+///   from the neighbouring stubs, `SyscallError::NotASyscall` if the export is not a syscall stub.
+pub unsafe fn fetch_nt_syscall(dw_sys_hash: u32) -> Result<NtSyscall, SyscallError> {
 
     if dw_sys_hash == 0 {
-        return Err("fetch_nt_syscall: dw_sys_hash argument is 0");
+        return Err(SyscallError::InvalidHash);
     }
 
     if let Some(syscall) = search_syscall_in_cache(dw_sys_hash) {
@@ -152,27 +155,22 @@ pub unsafe fn fetch_nt_syscall(dw_sys_hash: u32) -> Result<NtSyscall, &'static s
                 return Ok(nt_sys);
             }
 
-            // if hooked - scenario 1
-            if *func_address == 0xE9 {
+            // if hooked - scenario 1 (jmp at the start of the stub) or 2 (jmp after mov r10, rcx)
+            if *func_address == 0xE9 || *func_address.add(3) == 0xE9 {
                 if let Some(ssn) = find_syscall_number(func_address) {
                     nt_sys.dw_ssn = ssn;
                     SYSCALL_CACHE.push(nt_sys.clone());
                     return Ok(nt_sys);
                 }
+                return Err(SyscallError::Hooked { hash: dw_sys_hash });
             }
 
-            // if hooked - scenario 2
-            if *func_address.add(3) == 0xE9 {
-                if let Some(ssn) = find_syscall_number(func_address) {
-                    nt_sys.dw_ssn = ssn;
-                    SYSCALL_CACHE.push(nt_sys.clone());
-                    return Ok(nt_sys);
-                }
-            }
+            // Neither a syscall stub nor a known hook, e.g. an exported variable or a non-Nt function
+            return Err(SyscallError::NotASyscall { hash: dw_sys_hash });
         }
     }
 
-    Err("fetch_nt_syscall: Finished without finding syscall")
+    Err(SyscallError::SyscallNotFound { hash: dw_sys_hash })
 }
 
 /// Finds the syscall number by checking neighboring bytes for potential hooks.
@@ -293,11 +291,11 @@ mod tests {
 
         let nt_query_system_time_syscall_after_hook = match result_after {
             Ok(v) => {
-                remove_hook(hook);
+                remove_hook(hook).expect("Remove hook failed");
                 v
             },
             Err(e) => {
-                remove_hook(hook);
+                remove_hook(hook).expect("Remove hook failed");
                 panic!("[!] nt_query_system_time_syscall Failed: {}", e)
             }
         };
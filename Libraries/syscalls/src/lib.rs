@@ -3,6 +3,7 @@ pub use asm::run_direct_syscall;
 use asm::set_ssn;
 pub mod hells_gate;
 use hells_gate::{fetch_nt_syscall};
+use maldev_error::SyscallError;
 
 /// Prepares a system call by fetching the NT syscall using the provided hash.
 ///
@@ -10,17 +11,19 @@ use hells_gate::{fetch_nt_syscall};
 ///
 /// - `hash`: A 32-bit unsigned integer representing the hash value used to fetch the NT syscall.
 ///
-/// # Panics
+/// # Errors
 ///
-/// This function will panic if there is an error in fetching the NT syscall.
-/// The error message will be printed with the hash value.
-pub unsafe fn prepare_syscall(hash: u32) {
-    match fetch_nt_syscall(hash) {
-        Ok(syscall) => {
-            set_ssn(syscall.dw_ssn as usize);
-        },
-        Err(e) => {
-            panic!("[prepare_syscall] Error: {}", e);
-        }
-    }
-}
\ No newline at end of file
+/// Returns the `SyscallError` of `fetch_nt_syscall`, e.g. `SyscallError::Hooked` when the stub is
+/// hooked and its SSN cannot be recovered. The SSN is left untouched in that case.
+///
+/// # Safety
+///
+/// The function reads the export directory of the ntdll.dll found through the PEB and mutates the
+/// crate's global syscall cache, so it must not be called concurrently from several threads. The
+/// SSN is stored in a global read by `run_direct_syscall`, which must be called right after with
+/// the arguments of the syscall matching `hash`.
+pub unsafe fn prepare_syscall(hash: u32) -> Result<(), SyscallError> {
+    let syscall = fetch_nt_syscall(hash)?;
+    set_ssn(syscall.dw_ssn as usize);
+    Ok(())
+}
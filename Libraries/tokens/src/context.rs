@@ -0,0 +1,160 @@
+use std::sync::Mutex;
+use windows::Win32::Security::{TOKEN_ELEVATION, TOKEN_GROUPS, TOKEN_QUERY, TOKEN_USER, TokenElevation, TokenGroups, TokenSessionId, TokenUser};
+use windows::Win32::System::SystemServices::{SE_GROUP_ENABLED, SE_GROUP_USE_FOR_DENY_ONLY};
+
+use maldev_error::TokenError;
+
+use crate::integrity::{integrity_level, IntegrityLevel};
+use crate::user::{lookup_account, sid_to_string};
+use crate::Token;
+
+const LOCAL_SYSTEM_SID: &str = "S-1-5-18";
+const BUILTIN_ADMINISTRATORS_SID: &str = "S-1-5-32-544";
+
+static CURRENT: Mutex<Option<Context>> = Mutex::new(None);
+
+/// A group listed in a token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub sid: String,
+    pub name: Option<String>, // `None` for SIDs without an account (e.g. the logon SID)
+    pub enabled: bool,
+    pub deny_only: bool,      // Filtered group of a UAC restricted token, only used in deny ACEs
+}
+
+/// Security context of a token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Context {
+    pub user: String, // DOMAIN\user
+    pub sid: String,
+    pub integrity: IntegrityLevel,
+    pub elevated: bool,
+    pub session_id: u32,
+    pub groups: Vec<Group>,
+}
+
+impl Context {
+    /// Queries the security context of `token`.
+    ///
+    /// # Returns
+    /// * `Ok(Context)` - The context.
+    /// * `Err(TokenError)` - If the token cannot be queried.
+    pub fn from_token(token: &Token) -> Result<Self, TokenError> {
+        let token_user = token.query_information(TokenUser)?;
+        let user_sid = unsafe { (*(token_user.as_ptr() as *const TOKEN_USER)).User.Sid };
+        let elevation = token.query_information(TokenElevation)?;
+        let session_id = token.query_information(TokenSessionId)?;
+
+        Ok(Self {
+            user: lookup_account(user_sid)?,
+            sid: sid_to_string(user_sid)?,
+            integrity: integrity_level(token)?,
+            elevated: unsafe { (*(elevation.as_ptr() as *const TOKEN_ELEVATION)).TokenIsElevated != 0 },
+            session_id: unsafe { *(session_id.as_ptr() as *const u32) },
+            groups: token_groups(token)?,
+        })
+    }
+
+    /// Whether the token runs as LocalSystem.
+    pub fn is_system(&self) -> bool {
+        self.sid == LOCAL_SYSTEM_SID
+    }
+
+    /// Whether the BUILTIN\Administrators group is enabled in the token. This is `false` for the
+    /// filtered token of an administrator running without elevation.
+    pub fn is_admin(&self) -> bool {
+        self.groups.iter().any(|group| group.sid == BUILTIN_ADMINISTRATORS_SID && group.enabled && !group.deny_only)
+    }
+
+    /// Whether the account is an administrator, elevated or not.
+    pub fn is_admin_user(&self) -> bool {
+        self.groups.iter().any(|group| group.sid == BUILTIN_ADMINISTRATORS_SID)
+    }
+}
+
+/// Returns the security context the current thread runs with: the impersonation token if the
+/// thread is impersonating, the process token otherwise.
+///
+/// The process context is queried on the first call and cached, call [`refresh`] after changing
+/// the process token (e.g. privilege changes that affect groups). The context of an impersonating
+/// thread is queried on every call and never cached, as it only applies to that thread.
+pub fn current() -> Result<Context, TokenError> {
+    if let Some(token) = Token::open_current_thread(TOKEN_QUERY)? {
+        return Context::from_token(&token);
+    }
+
+    let mut cache = CURRENT.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(context) = cache.as_ref() {
+        return Ok(context.clone());
+    }
+    let context = Context::from_token(&Token::open_current_process(TOKEN_QUERY)?)?;
+    *cache = Some(context.clone());
+    Ok(context)
+}
+
+/// Queries the security context of the current thread again, see [`current`]. The cache is
+/// updated when the thread is not impersonating.
+pub fn refresh() -> Result<Context, TokenError> {
+    if let Some(token) = Token::open_current_thread(TOKEN_QUERY)? {
+        return Context::from_token(&token);
+    }
+
+    let context = Context::from_token(&Token::open_current_process(TOKEN_QUERY)?)?;
+    *CURRENT.lock().unwrap_or_else(|e| e.into_inner()) = Some(context.clone());
+    Ok(context)
+}
+
+fn token_groups(token: &Token) -> Result<Vec<Group>, TokenError> {
+    let buffer = token.query_information(TokenGroups)?;
+    let token_groups = buffer.as_ptr() as *const TOKEN_GROUPS;
+    let entries = unsafe {
+        std::slice::from_raw_parts((*token_groups).Groups.as_ptr(), (*token_groups).GroupCount as usize)
+    };
+
+    entries
+        .iter()
+        .map(|entry| {
+            Ok(Group {
+                sid: sid_to_string(entry.Sid)?,
+                name: lookup_account(entry.Sid).ok(),
+                enabled: entry.Attributes & SE_GROUP_ENABLED as u32 != 0,
+                deny_only: entry.Attributes & SE_GROUP_USE_FOR_DENY_ONLY as u32 != 0,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_context() {
+        let context = current().expect("[!] Failed to query the current context");
+        println!("[i] Current Context: {:?}", context);
+
+        assert!(context.user.contains('\\'));
+        assert!(context.sid.starts_with("S-1-5-"));
+        assert_eq!(context.session_id, refresh().unwrap().session_id);
+        // Every token contains the Everyone group
+        assert!(context.groups.iter().any(|group| group.sid == "S-1-1-0"));
+        // An elevated token always carries at least high integrity
+        if context.elevated {
+            assert!(context.integrity >= IntegrityLevel::High);
+        }
+        assert_eq!(current().unwrap(), context);
+    }
+
+    #[test]
+    fn test_admin_consistency() {
+        let context = current().expect("[!] Failed to query the current context");
+
+        // The enabled Administrators group only survives in full (elevated) tokens
+        if context.is_admin() {
+            assert!(context.elevated || context.integrity >= IntegrityLevel::High);
+            assert!(context.is_admin_user());
+        }
+        // The thread is not impersonating in tests, so the process token is used
+        assert!(Token::open_current_thread(TOKEN_QUERY).unwrap().is_none());
+    }
+}
@@ -0,0 +1,88 @@
+use windows::Win32::Security::{GetSidSubAuthority, GetSidSubAuthorityCount, TOKEN_MANDATORY_LABEL, TOKEN_QUERY, TokenIntegrityLevel};
+use windows::Win32::System::SystemServices::{SECURITY_MANDATORY_HIGH_RID, SECURITY_MANDATORY_LOW_RID, SECURITY_MANDATORY_MEDIUM_PLUS_RID, SECURITY_MANDATORY_MEDIUM_RID, SECURITY_MANDATORY_PROTECTED_PROCESS_RID, SECURITY_MANDATORY_SYSTEM_RID};
+
+use maldev_error::TokenError;
+
+use crate::Token;
+
+/// Mandatory integrity level of a token, ordered from least to most trusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IntegrityLevel {
+    Untrusted,
+    Low,
+    Medium,
+    MediumPlus,
+    High,
+    System,
+    Protected,
+}
+
+impl IntegrityLevel {
+    /// Maps the RID of a mandatory label SID to its level. Values between two well-known RIDs
+    /// belong to the lower level, as Windows does when comparing labels.
+    pub fn from_rid(rid: u32) -> Self {
+        match rid {
+            r if r < SECURITY_MANDATORY_LOW_RID as u32 => IntegrityLevel::Untrusted,
+            r if r < SECURITY_MANDATORY_MEDIUM_RID as u32 => IntegrityLevel::Low,
+            r if r < SECURITY_MANDATORY_MEDIUM_PLUS_RID => IntegrityLevel::Medium,
+            r if r < SECURITY_MANDATORY_HIGH_RID as u32 => IntegrityLevel::MediumPlus,
+            r if r < SECURITY_MANDATORY_SYSTEM_RID as u32 => IntegrityLevel::High,
+            r if r < SECURITY_MANDATORY_PROTECTED_PROCESS_RID as u32 => IntegrityLevel::System,
+            _ => IntegrityLevel::Protected,
+        }
+    }
+}
+
+/// Retrieves the RID of the mandatory label attached to `token`.
+///
+/// # Returns
+/// * `Ok(u32)` - The label RID (e.g. 0x2000 for medium integrity).
+/// * `Err(TokenError)` - If the token cannot be queried.
+pub fn integrity_rid(token: &Token) -> Result<u32, TokenError> {
+    let buffer = token.query_information(TokenIntegrityLevel)?;
+    let label = buffer.as_ptr() as *const TOKEN_MANDATORY_LABEL;
+
+    unsafe {
+        // The RID is the last sub-authority of the label SID
+        let sid = (*label).Label.Sid;
+        let count = *GetSidSubAuthorityCount(sid);
+        if count == 0 {
+            return Err(TokenError::InvalidSid);
+        }
+        Ok(*GetSidSubAuthority(sid, count as u32 - 1))
+    }
+}
+
+/// Retrieves the integrity level of `token`.
+pub fn integrity_level(token: &Token) -> Result<IntegrityLevel, TokenError> {
+    integrity_rid(token).map(IntegrityLevel::from_rid)
+}
+
+/// Retrieves the integrity level of the current process.
+pub fn current_integrity_level() -> Result<IntegrityLevel, TokenError> {
+    integrity_level(&Token::open_current_process(TOKEN_QUERY)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_rid() {
+        assert_eq!(IntegrityLevel::from_rid(0x0000), IntegrityLevel::Untrusted);
+        assert_eq!(IntegrityLevel::from_rid(0x1000), IntegrityLevel::Low);
+        assert_eq!(IntegrityLevel::from_rid(0x2000), IntegrityLevel::Medium);
+        assert_eq!(IntegrityLevel::from_rid(0x2100), IntegrityLevel::MediumPlus);
+        assert_eq!(IntegrityLevel::from_rid(0x3000), IntegrityLevel::High);
+        assert_eq!(IntegrityLevel::from_rid(0x4000), IntegrityLevel::System);
+        assert_eq!(IntegrityLevel::from_rid(0x5000), IntegrityLevel::Protected);
+        assert!(IntegrityLevel::from_rid(0x3000) > IntegrityLevel::Medium);
+    }
+
+    #[test]
+    fn test_current_integrity_level() {
+        let level = current_integrity_level().expect("[!] Failed to query the integrity level");
+        println!("[i] Current Integrity Level: {:?}", level);
+        assert!(level >= IntegrityLevel::Low);
+    }
+}
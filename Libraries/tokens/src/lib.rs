@@ -0,0 +1,137 @@
+pub mod context;
+pub mod integrity;
+pub mod privileges;
+pub mod user;
+
+use std::ffi::c_void;
+use std::mem::size_of;
+use maldev_error::{ApiError, OsCode, TokenError};
+use windows::Win32::Foundation::{CloseHandle, GetLastError, ERROR_NO_TOKEN, HANDLE};
+use windows::Win32::Security::{GetTokenInformation, TOKEN_ACCESS_MASK, TOKEN_INFORMATION_CLASS};
+use windows::Win32::System::Threading::{GetCurrentProcess, GetCurrentThread, OpenProcess, OpenProcessToken, OpenThreadToken, PROCESS_QUERY_LIMITED_INFORMATION};
+
+/// Access token handle that is closed when dropped.
+pub struct Token {
+    handle: HANDLE,
+}
+
+impl Token {
+    /// Opens the primary token of the current process.
+    ///
+    /// # Arguments
+    /// * `desired_access` - The access rights requested on the token (e.g. `TOKEN_QUERY`).
+    ///
+    /// # Returns
+    /// * `Ok(Token)` - The opened token.
+    /// * `Err(TokenError)` - If OpenProcessToken fails.
+    pub fn open_current_process(desired_access: TOKEN_ACCESS_MASK) -> Result<Self, TokenError> {
+        let mut handle = HANDLE::default();
+        unsafe { OpenProcessToken(GetCurrentProcess(), desired_access, &mut handle) }
+            .map_err(api_error("OpenProcessToken"))?;
+        Ok(Self { handle })
+    }
+
+    /// Opens the impersonation token of the current thread.
+    ///
+    /// The token is opened with the access check made against the process token, so this works
+    /// even when the impersonated identity cannot open its own token.
+    ///
+    /// # Arguments
+    /// * `desired_access` - The access rights requested on the token (e.g. `TOKEN_QUERY`).
+    ///
+    /// # Returns
+    /// * `Ok(Some(Token))` - The opened token.
+    /// * `Ok(None)` - If the thread is not impersonating.
+    /// * `Err(TokenError)` - If OpenThreadToken fails for another reason.
+    pub fn open_current_thread(desired_access: TOKEN_ACCESS_MASK) -> Result<Option<Self>, TokenError> {
+        let mut handle = HANDLE::default();
+        match unsafe { OpenThreadToken(GetCurrentThread(), desired_access, true, &mut handle) } {
+            Ok(()) => Ok(Some(Self { handle })),
+            Err(e) if e.code() == ERROR_NO_TOKEN.to_hresult() => Ok(None),
+            Err(e) => Err(api_error("OpenThreadToken")(e)),
+        }
+    }
+
+    /// Opens the primary token of another process.
+    ///
+    /// Only `PROCESS_QUERY_LIMITED_INFORMATION` is requested on the process, so this works for most
+    /// processes of the same user without SeDebugPrivilege.
+    ///
+    /// # Arguments
+    /// * `pid` - The identifier of the target process.
+    /// * `desired_access` - The access rights requested on the token (e.g. `TOKEN_QUERY`).
+    ///
+    /// # Returns
+    /// * `Ok(Token)` - The opened token.
+    /// * `Err(TokenError)` - If OpenProcess or OpenProcessToken fails.
+    pub fn open_process(pid: u32, desired_access: TOKEN_ACCESS_MASK) -> Result<Self, TokenError> {
+        let h_process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }
+            .map_err(api_error("OpenProcess"))?;
+
+        let result = Self::open_process_handle(h_process, desired_access);
+        unsafe {
+            let _ = CloseHandle(h_process);
+        }
+        result
+    }
+
+    /// Opens the primary token of a process from a handle the caller already holds, so the token
+    /// is guaranteed to belong to that process even if its PID gets reused.
+    ///
+    /// # Arguments
+    /// * `h_process` - A process handle with at least `PROCESS_QUERY_LIMITED_INFORMATION` access.
+    /// * `desired_access` - The access rights requested on the token (e.g. `TOKEN_QUERY`).
+    ///
+    /// # Returns
+    /// * `Ok(Token)` - The opened token.
+    /// * `Err(TokenError)` - If OpenProcessToken fails.
+    pub fn open_process_handle(h_process: HANDLE, desired_access: TOKEN_ACCESS_MASK) -> Result<Self, TokenError> {
+        let mut handle = HANDLE::default();
+        unsafe { OpenProcessToken(h_process, desired_access, &mut handle) }.map_err(api_error("OpenProcessToken"))?;
+        Ok(Self { handle })
+    }
+
+    /// Wraps an already opened token handle. The handle is closed when the `Token` is dropped.
+    ///
+    /// # Safety
+    /// `handle` must be a valid token handle owned by the caller.
+    pub unsafe fn from_raw(handle: HANDLE) -> Self {
+        Self { handle }
+    }
+
+    pub fn handle(&self) -> HANDLE {
+        self.handle
+    }
+
+    /// Retrieves a token information class into an 8 byte aligned buffer.
+    ///
+    /// # Returns
+    /// * `Ok(Vec<u64>)` - The raw buffer, to be cast to the structure matching `class`.
+    /// * `Err(TokenError)` - If GetTokenInformation fails.
+    pub(crate) fn query_information(&self, class: TOKEN_INFORMATION_CLASS) -> Result<Vec<u64>, TokenError> {
+        // First call only retrieves the required size
+        let mut return_length: u32 = 0;
+        let _ = unsafe { GetTokenInformation(self.handle, class, None, 0, &mut return_length) };
+        if return_length == 0 {
+            return Err(TokenError::Api(ApiError::new("GetTokenInformation", OsCode::Win32(unsafe { GetLastError() }.0))));
+        }
+
+        let mut buffer = vec![0u64; (return_length as usize).div_ceil(size_of::<u64>())];
+        unsafe { GetTokenInformation(self.handle, class, Some(buffer.as_mut_ptr() as *mut c_void), return_length, &mut return_length) }
+            .map_err(api_error("GetTokenInformation"))?;
+        Ok(buffer)
+    }
+}
+
+/// Wraps a failed windows-rs call into a `TokenError` naming the API.
+pub(crate) fn api_error(api: &'static str) -> impl Fn(windows::core::Error) -> TokenError {
+    move |e| TokenError::Api(ApiError::new(api, OsCode::HResult(e.code().0)))
+}
+
+impl Drop for Token {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.handle);
+        }
+    }
+}
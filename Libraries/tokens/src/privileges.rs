@@ -0,0 +1,121 @@
+use std::mem::size_of;
+use windows::core::{PCWSTR, PWSTR};
+use windows::Win32::Foundation::{GetLastError, ERROR_NOT_ALL_ASSIGNED, LUID};
+use windows::Win32::Security::{AdjustTokenPrivileges, LookupPrivilegeNameW, LookupPrivilegeValueW, LUID_AND_ATTRIBUTES, SE_PRIVILEGE_ENABLED, SE_PRIVILEGE_ENABLED_BY_DEFAULT, TOKEN_ADJUST_PRIVILEGES, TOKEN_PRIVILEGES, TOKEN_PRIVILEGES_ATTRIBUTES, TOKEN_QUERY, TokenPrivileges};
+
+use maldev_error::TokenError;
+
+use crate::{api_error, Token};
+
+/// A privilege held by a token.
+#[derive(Debug, Clone)]
+pub struct Privilege {
+    pub name: String,
+    pub enabled: bool,
+    pub enabled_by_default: bool,
+}
+
+/// Enables a privilege on the current process token.
+///
+/// # Arguments
+/// * `name` - The privilege name, e.g. `SE_DEBUG_NAME`.
+///
+/// # Returns
+/// * `Ok(())` - If the privilege is now enabled.
+/// * `Err(TokenError)` - If the lookup or the adjustment fails. `TokenError::PrivilegeNotHeld` when the
+///   token does not hold the privilege at all (AdjustTokenPrivileges reports success with
+///   ERROR_NOT_ALL_ASSIGNED).
+pub fn enable_privilege(name: PCWSTR) -> Result<(), TokenError> {
+    set_privilege(name, SE_PRIVILEGE_ENABLED)
+}
+
+/// Disables a privilege on the current process token.
+pub fn disable_privilege(name: PCWSTR) -> Result<(), TokenError> {
+    set_privilege(name, TOKEN_PRIVILEGES_ATTRIBUTES(0))
+}
+
+fn set_privilege(name: PCWSTR, attributes: TOKEN_PRIVILEGES_ATTRIBUTES) -> Result<(), TokenError> {
+    let token = Token::open_current_process(TOKEN_ADJUST_PRIVILEGES | TOKEN_QUERY)?;
+
+    let mut luid = LUID::default();
+    unsafe { LookupPrivilegeValueW(PCWSTR::null(), name, &mut luid) }
+        .map_err(api_error("LookupPrivilegeValueW"))?;
+
+    let new_state = TOKEN_PRIVILEGES {
+        PrivilegeCount: 1,
+        Privileges: [LUID_AND_ATTRIBUTES { Luid: luid, Attributes: attributes }],
+    };
+
+    unsafe {
+        AdjustTokenPrivileges(token.handle(), false, Some(&new_state), size_of::<TOKEN_PRIVILEGES>() as u32, None, None)
+            .map_err(api_error("AdjustTokenPrivileges"))?;
+
+        if GetLastError() == ERROR_NOT_ALL_ASSIGNED {
+            return Err(TokenError::PrivilegeNotHeld);
+        }
+    }
+    Ok(())
+}
+
+/// Lists every privilege present in the current process token.
+///
+/// # Returns
+/// * `Ok(Vec<Privilege>)` - The privileges with their state.
+/// * `Err(TokenError)` - If the token cannot be opened or queried.
+pub fn privileges() -> Result<Vec<Privilege>, TokenError> {
+    let token = Token::open_current_process(TOKEN_QUERY)?;
+    let buffer = token.query_information(TokenPrivileges)?;
+
+    let token_privileges = buffer.as_ptr() as *const TOKEN_PRIVILEGES;
+    let entries = unsafe {
+        std::slice::from_raw_parts((*token_privileges).Privileges.as_ptr(), (*token_privileges).PrivilegeCount as usize)
+    };
+
+    entries
+        .iter()
+        .map(|entry| {
+            Ok(Privilege {
+                name: privilege_name(&entry.Luid)?,
+                enabled: entry.Attributes.0 & SE_PRIVILEGE_ENABLED.0 != 0,
+                enabled_by_default: entry.Attributes.0 & SE_PRIVILEGE_ENABLED_BY_DEFAULT.0 != 0,
+            })
+        })
+        .collect()
+}
+
+/// Returns the names of the privileges currently enabled on the current process token.
+pub fn enabled_privileges() -> Result<Vec<String>, TokenError> {
+    Ok(privileges()?.into_iter().filter(|p| p.enabled).map(|p| p.name).collect())
+}
+
+fn privilege_name(luid: &LUID) -> Result<String, TokenError> {
+    let mut name = [0u16; 256];
+    let mut name_len = name.len() as u32;
+    unsafe { LookupPrivilegeNameW(PCWSTR::null(), luid, PWSTR(name.as_mut_ptr()), &mut name_len) }
+        .map_err(api_error("LookupPrivilegeNameW"))?;
+    Ok(String::from_utf16_lossy(&name[..name_len as usize]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maldev_error::ErrorKind;
+    use windows::core::w;
+    use windows::Win32::Security::SE_CHANGE_NOTIFY_NAME;
+
+    #[test]
+    fn test_enabled_privileges() {
+        // SeChangeNotifyPrivilege is granted and enabled for every user
+        let enabled = enabled_privileges().expect("[!] Failed to list privileges");
+        assert!(enabled.iter().any(|name| name == "SeChangeNotifyPrivilege"));
+    }
+
+    #[test]
+    fn test_enable_privilege() {
+        enable_privilege(SE_CHANGE_NOTIFY_NAME).expect("[!] Failed to enable SeChangeNotifyPrivilege");
+        match enable_privilege(w!("SeNotARealPrivilege")) {
+            Err(TokenError::Api(e)) => assert_eq!(e.kind(), ErrorKind::NotFound),
+            other => panic!("[!] Unexpected result for an unknown privilege: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::ffi::c_void;
+use windows::core::{PCWSTR, PWSTR};
+use windows::Win32::Foundation::{LocalFree, HLOCAL, PSID};
+use windows::Win32::Security::Authorization::{ConvertSidToStringSidW, ConvertStringSidToSidW};
+use windows::Win32::Security::{LookupAccountSidW, SID_NAME_USE, TOKEN_USER, TokenUser};
+
+use maldev_error::TokenError;
+
+use crate::{api_error, Token};
+
+/// Resolves the account that owns `token`.
+///
+/// # Returns
+/// * `Ok(String)` - The account name formatted as `DOMAIN\user`.
+/// * `Err(TokenError)` - If the token cannot be queried or the SID cannot be resolved.
+pub fn token_user(token: &Token) -> Result<String, TokenError> {
+    let buffer = token.query_information(TokenUser)?;
+    let token_user = buffer.as_ptr() as *const TOKEN_USER;
+    lookup_account(unsafe { (*token_user).User.Sid })
+}
+
+/// Retrieves the SID of the account that owns `token`, in its string form (e.g. `S-1-5-18`).
+pub fn token_user_sid(token: &Token) -> Result<String, TokenError> {
+    let buffer = token.query_information(TokenUser)?;
+    let token_user = buffer.as_ptr() as *const TOKEN_USER;
+    sid_to_string(unsafe { (*token_user).User.Sid })
+}
+
+/// Resolves a SID to `DOMAIN\name`.
+pub(crate) fn lookup_account(sid: PSID) -> Result<String, TokenError> {
+    let mut name = [0u16; 256];
+    let mut name_len = name.len() as u32;
+    let mut domain = [0u16; 256];
+    let mut domain_len = domain.len() as u32;
+    let mut sid_type = SID_NAME_USE::default();
+
+    unsafe {
+        LookupAccountSidW(
+            PCWSTR::null(),
+            sid,
+            PWSTR(name.as_mut_ptr()),
+            &mut name_len,
+            PWSTR(domain.as_mut_ptr()),
+            &mut domain_len,
+            &mut sid_type,
+        )
+    }
+    .map_err(api_error("LookupAccountSidW"))?;
+
+    Ok(format!(
+        "{}\\{}",
+        String::from_utf16_lossy(&domain[..domain_len as usize]),
+        String::from_utf16_lossy(&name[..name_len as usize])
+    ))
+}
+
+/// Resolves a SID given in its string form (e.g. `S-1-5-18`) to `DOMAIN\name`.
+///
+/// # Returns
+/// * `Ok(String)` - The account name.
+/// * `Err(TokenError)` - If the string is not a valid SID or no account maps to it.
+pub fn lookup_sid(sid: &str) -> Result<String, TokenError> {
+    let wide: Vec<u16> = sid.encode_utf16().chain(std::iter::once(0)).collect();
+    let mut psid = PSID::default();
+    unsafe {
+        ConvertStringSidToSidW(PCWSTR(wide.as_ptr()), &mut psid).map_err(api_error("ConvertStringSidToSidW"))?;
+        let result = lookup_account(psid);
+        // The SID is allocated by the system with LocalAlloc
+        let _ = LocalFree(HLOCAL(psid.0));
+        result
+    }
+}
+
+/// Converts a SID to its string form.
+pub(crate) fn sid_to_string(sid: PSID) -> Result<String, TokenError> {
+    let mut string_sid = PWSTR::null();
+    unsafe {
+        ConvertSidToStringSidW(sid, &mut string_sid).map_err(api_error("ConvertSidToStringSidW"))?;
+        let result = string_sid.to_string().unwrap_or_default();
+        // The string is allocated by the system with LocalAlloc
+        let _ = LocalFree(HLOCAL(string_sid.0 as *mut c_void));
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use windows::Win32::Security::TOKEN_QUERY;
+
+    #[test]
+    fn test_token_user() {
+        let token = Token::open_current_process(TOKEN_QUERY).expect("[!] Failed to open the process token");
+        let user = token_user(&token).expect("[!] Failed to resolve the token user");
+        println!("[i] Token User: {}", user);
+        assert!(user.contains('\\'));
+
+        let token = Token::open_process(std::process::id(), TOKEN_QUERY).expect("[!] Failed to open the process token by pid");
+        assert_eq!(token_user(&token).unwrap(), user);
+
+        let sid = token_user_sid(&token).expect("[!] Failed to convert the token user SID");
+        assert!(sid.starts_with("S-1-5-"));
+        assert_eq!(lookup_sid(&sid).unwrap(), user);
+        assert!(lookup_sid("not a sid").is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use maldev_error::CompressionError;
+
+// LZSS stream layout:
+//   [original length: u32 LE] followed by groups of one flag byte and up to 8 tokens.
+//   Flag bit i set   -> token i is a literal byte.
+//   Flag bit i clear -> token i is a 2 byte back-reference: 12 bit offset - 1, 4 bit length - MIN_MATCH.
+
+const WINDOW_SIZE: usize = 4096;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 18;
+const HASH_SIZE: usize = 4096;
+const MAX_CHAIN: usize = 64; // Candidates inspected per position, trades ratio for speed
+const HEADER_SIZE: usize = 4;
+
+fn hash3(data: &[u8], pos: usize) -> usize {
+    let value = (data[pos] as usize) << 16 | (data[pos + 1] as usize) << 8 | data[pos + 2] as usize;
+    (value.wrapping_mul(2654435761) >> 12) % HASH_SIZE
+}
+
+fn insert_position(data: &[u8], pos: usize, head: &mut [usize], prev: &mut [usize]) {
+    if pos + MIN_MATCH <= data.len() {
+        let h = hash3(data, pos);
+        prev[pos % WINDOW_SIZE] = head[h];
+        head[h] = pos;
+    }
+}
+
+/// Compresses `data` with LZSS (4 KB window, matches of 3 to 18 bytes).
+///
+/// # Arguments
+/// * `data` - The buffer to compress. It must be smaller than 4 GB.
+///
+/// # Returns
+/// * `Ok(Vec<u8>)` - The compressed stream, to be restored with [`decompress`].
+/// * `Err(CompressionError)` - If `data` does not fit in the 32 bit length header.
+pub fn compress(data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+    let original_len = u32::try_from(data.len()).map_err(|_| CompressionError::InputTooLarge)?;
+    let mut output = Vec::with_capacity(HEADER_SIZE + data.len() + data.len() / 8 + 1);
+    output.extend_from_slice(&original_len.to_le_bytes());
+
+    // Hash chains: head[h] is the latest position with hash h, prev[pos % WINDOW_SIZE] the previous one
+    let mut head = vec![usize::MAX; HASH_SIZE];
+    let mut prev = vec![usize::MAX; WINDOW_SIZE];
+
+    let mut flag_index = 0;
+    let mut token_count = 8;
+    let mut pos = 0;
+
+    while pos < data.len() {
+        if token_count == 8 {
+            flag_index = output.len();
+            output.push(0);
+            token_count = 0;
+        }
+
+        // Find the longest match inside the window
+        let mut best_len = 0;
+        let mut best_offset = 0;
+        if pos + MIN_MATCH <= data.len() {
+            let max_len = MAX_MATCH.min(data.len() - pos);
+            let mut candidate = head[hash3(data, pos)];
+            let mut chain = 0;
+            while candidate != usize::MAX && pos - candidate <= WINDOW_SIZE && chain < MAX_CHAIN {
+                let len = data[candidate..].iter().zip(&data[pos..pos + max_len]).take_while(|(a, b)| a == b).count();
+                if len > best_len {
+                    best_len = len;
+                    best_offset = pos - candidate;
+                    if len == max_len {
+                        break;
+                    }
+                }
+                let next = prev[candidate % WINDOW_SIZE];
+                // Entries older than the window have been overwritten by newer positions
+                if next == usize::MAX || next >= candidate {
+                    break;
+                }
+                candidate = next;
+                chain += 1;
+            }
+        }
+
+        if best_len >= MIN_MATCH {
+            let offset = best_offset - 1;
+            output.push(offset as u8);
+            output.push(((offset >> 8) << 4) as u8 | (best_len - MIN_MATCH) as u8);
+            for p in pos..pos + best_len {
+                insert_position(data, p, &mut head, &mut prev);
+            }
+            pos += best_len;
+        } else {
+            output[flag_index] |= 1 << token_count;
+            output.push(data[pos]);
+            insert_position(data, pos, &mut head, &mut prev);
+            pos += 1;
+        }
+        token_count += 1;
+    }
+
+    Ok(output)
+}
+
+/// Restores a buffer produced by [`compress`].
+///
+/// # Arguments
+/// * `data` - The compressed stream.
+///
+/// # Returns
+/// * `Ok(Vec<u8>)` - The original data.
+/// * `Err(CompressionError)` - If the stream is truncated or contains an invalid back-reference.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+    if data.len() < HEADER_SIZE {
+        return Err(CompressionError::InvalidHeader);
+    }
+    let original_len = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    // A back-reference expands 2 bytes into at most MAX_MATCH, which bounds any honest header
+    if original_len > (data.len() - HEADER_SIZE) * MAX_MATCH / 2 + MAX_MATCH {
+        return Err(CompressionError::InvalidHeader);
+    }
+
+    let mut output = Vec::with_capacity(original_len);
+    let mut pos = HEADER_SIZE;
+
+    while output.len() < original_len {
+        let flags = *data.get(pos).ok_or(CompressionError::Truncated)?;
+        pos += 1;
+
+        for bit in 0..8 {
+            if output.len() >= original_len {
+                break;
+            }
+            if flags & (1 << bit) != 0 {
+                output.push(*data.get(pos).ok_or(CompressionError::Truncated)?);
+                pos += 1;
+            } else {
+                let (low, high) = match data.get(pos..pos + 2) {
+                    Some(token) => (token[0] as usize, token[1] as usize),
+                    None => return Err(CompressionError::Truncated),
+                };
+                pos += 2;
+
+                let offset = (low | (high >> 4) << 8) + 1;
+                let len = (high & 0x0F) + MIN_MATCH;
+                if offset > output.len() || output.len() + len > original_len {
+                    return Err(CompressionError::InvalidBackReference);
+                }
+                // Byte by byte copy, matches are allowed to overlap the bytes they produce
+                let start = output.len() - offset;
+                for i in 0..len {
+                    output.push(output[start + i]);
+                }
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_empty() {
+        let compressed = compress(&[]).unwrap();
+        assert_eq!(compressed.len(), HEADER_SIZE);
+        assert_eq!(decompress(&compressed).unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_roundtrip_text() {
+        let data = b"Malware Development Is Cool. Malware Development Is Cool. Malware Development Is Bad.".repeat(20);
+        let compressed = compress(&data).unwrap();
+        assert!(compressed.len() < data.len() / 4);
+        assert_eq!(decompress(&compressed).unwrap(), data);
+    }
+
+    #[test]
+    fn test_roundtrip_overlapping_run() {
+        let data = vec![0x90u8; 10_000];
+        let compressed = compress(&data).unwrap();
+        assert!(compressed.len() < 1500);
+        assert_eq!(decompress(&compressed).unwrap(), data);
+    }
+
+    #[test]
+    fn test_roundtrip_incompressible() {
+        // xorshift output, barely any repetitions
+        let mut state: u32 = 0x12345678;
+        let data: Vec<u8> = (0..5000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        let compressed = compress(&data).unwrap();
+        assert!(compressed.len() <= HEADER_SIZE + data.len() + data.len() / 8 + 1);
+        assert_eq!(decompress(&compressed).unwrap(), data);
+    }
+
+    #[test]
+    fn test_decompress_rejects_corrupted_streams() {
+        assert_eq!(decompress(&[0x01, 0x00]), Err(CompressionError::InvalidHeader));
+
+        let compressed = compress(b"abcabcabcabcabcabc").unwrap();
+        assert_eq!(decompress(&compressed[..compressed.len() - 1]), Err(CompressionError::Truncated));
+
+        // Back-reference pointing before the start of the output
+        assert_eq!(decompress(&[0x05, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00]), Err(CompressionError::InvalidBackReference));
+    }
+}
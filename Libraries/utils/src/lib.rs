@@ -1,3 +1,4 @@
+pub mod compression;
 pub mod hash;
 
 use std::arch::asm;
@@ -94,7 +95,12 @@ unsafe fn get_nt_headers(module_handle: HMODULE) -> *mut IMAGE_NT_HEADERS64 {
 ///
 /// # Returns
 /// * `Option<*const IMAGE_EXPORT_DIRECTORY>` - A pointer to the export directory, or `None` if not found.
-unsafe fn get_export_directory(module_handle: HMODULE) -> Option<*const IMAGE_EXPORT_DIRECTORY> {
+///
+/// # Safety
+/// `module_handle` must be the base address of a PE image mapped in the current process (e.g. a
+/// loaded module) that stays mapped while the returned pointer is used. Its headers are read
+/// without any bounds checks.
+pub unsafe fn get_export_directory(module_handle: HMODULE) -> Option<*const IMAGE_EXPORT_DIRECTORY> {
     let nt_headers = get_nt_headers(module_handle);
     if nt_headers.is_null() {
         return None;
@@ -170,25 +176,25 @@ mod tests {
 
         unsafe {
             let dll_name = w!("non_existent_dll.DLL");
-            let dll_hash = crc32.compute_hash(unsafe { dll_name.to_string().unwrap().to_uppercase() }.unwrap().as_bytes());
+            let dll_hash = crc32.compute_hash(dll_name.to_string().unwrap().to_uppercase().as_bytes());
 
             let exported_functions = get_dll_exported_functions_by_hash(dll_hash);
             assert_eq!(exported_functions.len(), 0);
 
             let dll_name = w!("NTDLL.DLL");
-            let dll_hash = crc32.compute_hash(unsafe { dll_name.to_string().to_uppercase() }.unwrap().as_bytes());
+            let dll_hash = crc32.compute_hash(dll_name.to_string().unwrap().to_uppercase().as_bytes());
 
             let exported_functions = get_dll_exported_functions_by_hash(dll_hash);
             assert_ne!(exported_functions.len(), 0);
 
             let dll_name = w!("Kernel32.dll");
-            let dll_hash = crc32.compute_hash(unsafe { dll_name.to_string().to_uppercase() }.unwrap().as_bytes());
+            let dll_hash = crc32.compute_hash(dll_name.to_string().unwrap().to_uppercase().as_bytes());
 
             let exported_functions = get_dll_exported_functions_by_hash(dll_hash);
             assert_ne!(exported_functions.len(), 0);
 
             let dll_name = w!("gibberish");
-            let dll_hash = crc32.compute_hash(unsafe { dll_name.to_string().to_uppercase() }.unwrap().as_bytes());
+            let dll_hash = crc32.compute_hash(dll_name.to_string().unwrap().to_uppercase().as_bytes());
 
             let exported_functions = get_dll_exported_functions_by_hash(dll_hash);
             assert_eq!(exported_functions.len(), 0);